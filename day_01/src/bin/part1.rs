@@ -1,4 +1,4 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 
 pub fn sum_first_and_last_digits(line: &str) -> u32 {
     let first_digit: u32 = line.chars().next().unwrap().to_digit(10).unwrap_or(0);
@@ -6,46 +6,53 @@ pub fn sum_first_and_last_digits(line: &str) -> u32 {
     return first_digit * 10 + last_digit;
 }
 
-fn advent_of_code(input_text: &str) -> String {
-    let input: String = String::from(input_text);
-    let mut input_numeric: String = String::from("");
+// Lines are independent, so they are processed as they are read
+pub fn sum_calibration<I>(lines: I) -> Result<u64, String>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    // For each line, get first and last digit and convert into a number. Sum all numbers.
+    let mut sum: u64 = 0;
 
-    // Remove all non-numeric characters
-    for line in input.lines() {
-        for char in line.chars() {
-            if char.is_numeric() {
-                input_numeric.push(char);
-            }
-        }
-        input_numeric.push('\n');
+    for line in lines {
+        // Remove all non-numeric characters
+        let line_numeric: String = line.as_ref().chars().filter(|c| c.is_numeric()).collect();
+        let value = sum_first_and_last_digits(&line_numeric) as u64;
+        sum = match sum.checked_add(value) {
+            Some(sum) => sum,
+            None => return Err(String::from("Sum of calibration values too large")),
+        };
     }
 
-    // For each line, get first and last digit and convert into a number. Sum all numbers.
-    let mut sum: u32 = 0;
+    return Ok(sum);
+}
 
-    for line in input_numeric.lines() {
-        sum += sum_first_and_last_digits(&line);
+#[cfg_attr(not(test), allow(unused))]
+fn advent_of_code(input_text: &str) -> String {
+    match sum_calibration(input_text.lines()) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => panic!("{}", error),
     }
-
-    return sum.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Stream input lines from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part1", &[]);
+    let lines = io.lines();
 
     // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    let output: String = match sum_calibration(lines) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
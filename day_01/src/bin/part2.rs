@@ -1,121 +1,296 @@
-use std::collections::HashMap;
-use test_utils::{get_file_content, get_full_path, write_file_content};
-mod part1;
+use std::collections::{HashMap, VecDeque};
+use test_utils::{Flag, SolverIo};
 
+// Vocabulary of words that stand for a digit
 struct NumberNames {
-    number_map: HashMap<String, char>,
-    starting_with_map: HashMap<char, Vec<String>>,
+    words: Vec<(String, u32)>,
+    ignore_case: bool,
 }
 
 impl NumberNames {
     fn new() -> Self {
-        let mut number_map: HashMap<String, char> = HashMap::new();
-        let mut starting_with_map: HashMap<char, Vec<String>> = HashMap::new();
-
-        let names: Vec<String> = vec![
-            String::from("zero"),
-            String::from("one"),
-            String::from("two"),
-            String::from("three"),
-            String::from("four"),
-            String::from("five"),
-            String::from("six"),
-            String::from("seven"),
-            String::from("eight"),
-            String::from("nine"),
-        ];
-
-        for (i, name) in names.iter().enumerate() {
-            let i_char: Option<char> = char::from_digit(i as u32, 10);
-            number_map.insert(name.clone(), i_char.unwrap());
-            starting_with_map
-                .entry(name.chars().next().unwrap())
-                .or_insert_with(Vec::new)
-                .push(name.clone());
-        }
+        NumberNames::from_words(NumberNames::language("english").unwrap())
+    }
 
+    // An empty name would match at every position, so it is rejected
+    fn from_words(words: impl IntoIterator<Item = (String, u32)>) -> Self {
+        let words: Vec<(String, u32)> = words.into_iter().collect();
+        if let Some((_, digit)) = words.iter().find(|(word, _)| word.is_empty()) {
+            panic!("Empty number name for digit {}", digit);
+        }
         NumberNames {
-            number_map,
-            starting_with_map,
+            words,
+            ignore_case: false,
         }
     }
 
-    fn names_starting_with_and_length(&self, character: char, len: usize) -> Option<Vec<&String>> {
-        if let Some(starting_with) = self.starting_with_map.get(&character) {
-            let names: Vec<&String> = starting_with
-                .iter()
-                .filter(|&name| name.len() <= len)
-                .collect();
-            return Some(names);
+    // Built-in vocabularies, the position of each name is its digit
+    fn language(name: &str) -> Option<Vec<(String, u32)>> {
+        let names: Vec<&str> = match name {
+            "english" => vec![
+                "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+            ],
+            "spanish" => vec![
+                "cero", "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve",
+            ],
+            "french" => vec![
+                "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+            ],
+            // There is no roman numeral for zero
+            "roman" => vec!["", "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX"],
+            _ => return None,
+        };
+        let words = names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| !name.is_empty())
+            .map(|(i, name)| (name.to_string(), i as u32))
+            .collect();
+        Some(words)
+    }
+
+    // One "key = value" per line, empty lines and the ones starting with '#' are skipped:
+    // language = english | spanish | french | roman
+    // ignore_case = true | false
+    // <word> = <digit>
+    fn from_config(config: &str) -> Self {
+        let mut number_names = NumberNames::from_words(Vec::new());
+        for line in config.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => panic!("Invalid vocabulary line: {}", line),
+            };
+            match key {
+                "language" => match NumberNames::language(value) {
+                    Some(words) => number_names.words.extend(words),
+                    None => panic!("Unknown language: {}", value),
+                },
+                "ignore_case" => {
+                    number_names.ignore_case = value
+                        .parse::<bool>()
+                        .unwrap_or_else(|_| panic!("Invalid ignore_case value: {}", value));
+                }
+                "" => panic!("Empty number name in: {}", line),
+                word => match value.parse::<u32>() {
+                    Ok(digit) if digit < 10 => number_names.words.push((word.to_string(), digit)),
+                    _ => panic!("Invalid digit for {}: {}", word, value),
+                },
+            }
+        }
+        number_names
+    }
+
+    // Unicode lowercase when it maps to a single char, so match positions stay the same
+    fn fold(&self, character: char) -> char {
+        if !self.ignore_case {
+            return character;
+        }
+        let mut lowercase = character.to_lowercase();
+        match (lowercase.next(), lowercase.next()) {
+            (Some(lower), None) => lower,
+            _ => character,
         }
-        None
     }
+}
 
-    fn get_number_at_beginning(&self, string_data: &str) -> Option<String> {
-        let string_len: usize = string_data.len();
-        let first_char: char = string_data.chars().next().unwrap();
+// Position in chars of a word found in a line, end is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DigitMatch {
+    start: usize,
+    end: usize,
+    digit: u32,
+}
 
-        if let Some(candidates) = self.names_starting_with_and_length(first_char, string_len) {
-            for candidate in candidates {
-                if string_data.starts_with(candidate) {
-                    return Some(candidate.clone());
+// Aho-Corasick automaton over the digits and the vocabulary words
+struct DigitScanner {
+    number_names: NumberNames,
+    transitions: Vec<HashMap<char, usize>>,
+    // Longest proper suffix of the node that is also a node
+    fail: Vec<usize>,
+    // Words ending at each node, including the ones reached by the fail links (len, digit)
+    output: Vec<Vec<(usize, u32)>>,
+}
+
+impl DigitScanner {
+    fn new(number_names: NumberNames) -> Self {
+        let words = number_names.words.clone();
+        let mut scanner = DigitScanner {
+            number_names,
+            transitions: vec![HashMap::new()],
+            fail: vec![0],
+            output: vec![Vec::new()],
+        };
+
+        let digits = (0..10).map(|digit| (char::from_digit(digit, 10).unwrap().to_string(), digit));
+        for (word, digit) in digits.chain(words) {
+            scanner.insert(&word, digit);
+        }
+        scanner.build_fail_links();
+        scanner
+    }
+
+    fn insert(&mut self, word: &str, digit: u32) {
+        let mut node: usize = 0;
+        for character in word.chars() {
+            let character = self.number_names.fold(character);
+            node = match self.transitions[node].get(&character) {
+                Some(&next) => next,
+                None => {
+                    let next = self.transitions.len();
+                    self.transitions.push(HashMap::new());
+                    self.fail.push(0);
+                    self.output.push(Vec::new());
+                    self.transitions[node].insert(character, next);
+                    next
                 }
+            };
+        }
+        self.output[node].push((word.chars().count(), digit));
+    }
+
+    // Breadth first, so the fail node of a node is always complete before it
+    fn build_fail_links(&mut self) {
+        let mut queue: VecDeque<usize> = self.transitions[0].values().cloned().collect();
+        while let Some(node) = queue.pop_front() {
+            let children: Vec<(char, usize)> = self.transitions[node]
+                .iter()
+                .map(|(&character, &child)| (character, child))
+                .collect();
+            for (character, child) in children {
+                let fail = self.next_state(self.fail[node], character);
+                self.fail[child] = fail;
+                let inherited = self.output[fail].clone();
+                self.output[child].extend(inherited);
+                queue.push_back(child);
             }
         }
-        None
     }
-}
 
-fn replace_digits_string_to_int(line: &mut String, number_names: &NumberNames) {
-    let mut line_formatted: String = String::from("");
-    for mut i in 0..line.len() {
-        let char_i: char = line.chars().nth(i).unwrap();
-        // Check if char i is numeric
-        if char_i.is_numeric() {
-            line_formatted.push(char_i);
-            continue;
+    fn next_state(&self, mut node: usize, character: char) -> usize {
+        loop {
+            if let Some(&next) = self.transitions[node].get(&character) {
+                return next;
+            }
+            if node == 0 {
+                return 0;
+            }
+            node = self.fail[node];
         }
-        // Check if char i is the beginning of a number name
-        let numer_found: Option<String> = number_names.get_number_at_beginning(&line[i..]);
-        if numer_found.is_some() {
-            let number_name: String = numer_found.unwrap();
-            let number_name_len = number_name.len();
-            line_formatted.push(number_names.number_map[&number_name]);
-            i += number_name_len - 1;
+    }
+
+    // Every match in a single pass, overlapping ones included
+    fn for_each_match(&self, line: &str, mut callback: impl FnMut(DigitMatch)) {
+        let mut node: usize = 0;
+        for (index, character) in line.chars().enumerate() {
+            node = self.next_state(node, self.number_names.fold(character));
+            for &(len, digit) in self.output[node].iter() {
+                callback(DigitMatch {
+                    start: index + 1 - len,
+                    end: index + 1,
+                    digit,
+                });
+            }
         }
     }
-    *line = line_formatted;
+
+    #[cfg_attr(not(test), allow(unused))]
+    fn find_matches(&self, line: &str) -> Vec<DigitMatch> {
+        let mut matches: Vec<DigitMatch> = Vec::new();
+        self.for_each_match(line, |digit_match| matches.push(digit_match));
+        matches
+    }
+
+    // First digit is the word that starts first and last digit the one that ends last,
+    // the longest word on ties so "VIII" is an 8 and not a 5 or a 1
+    fn calibration_digits(&self, line: &str) -> Option<(u32, u32)> {
+        let mut first: Option<DigitMatch> = None;
+        let mut last: Option<DigitMatch> = None;
+        self.for_each_match(line, |found| {
+            let is_first = first.is_none_or(|first| {
+                found.start < first.start || (found.start == first.start && found.end > first.end)
+            });
+            if is_first {
+                first = Some(found);
+            }
+            let is_last = last.is_none_or(|last| {
+                found.end > last.end || (found.end == last.end && found.start < last.start)
+            });
+            if is_last {
+                last = Some(found);
+            }
+        });
+        Some((first?.digit, last?.digit))
+    }
+
+    fn calibration_value(&self, line: &str) -> Option<u32> {
+        let (first_digit, last_digit) = self.calibration_digits(line)?;
+        Some(first_digit * 10 + last_digit)
+    }
 }
 
-fn advent_of_code(input_text: &str) -> String {
-    let input: String = String::from(input_text);
-    let mut sum: u32 = 0;
-    let number_names: NumberNames = NumberNames::new();
+fn sum_calibration_values<I>(lines: I, number_names: NumberNames) -> Result<u64, String>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let mut sum: u64 = 0;
+    let scanner: DigitScanner = DigitScanner::new(number_names);
 
-    for line_str in input.lines() {
-        let mut line: String = line_str.to_string();
-        replace_digits_string_to_int(&mut line, &number_names);
-        sum += part1::sum_first_and_last_digits(&line);
+    for line in lines {
+        let line: &str = line.as_ref();
+        let value = match scanner.calibration_value(line) {
+            Some(value) => value as u64,
+            None => panic!("No digit in line: {}", line),
+        };
+        sum = match sum.checked_add(value) {
+            Some(sum) => sum,
+            None => return Err(String::from("Sum of calibration values too large")),
+        };
     }
 
-    return sum.to_string();
+    return Ok(sum);
 }
 
-fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
+#[cfg_attr(not(test), allow(unused))]
+fn advent_of_code(input_text: &str) -> String {
+    match sum_calibration_values(input_text.lines(), NumberNames::new()) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => panic!("{}", error),
     }
+}
+
+fn main() {
+    // Stream input lines from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(
+        env!("CARGO_MANIFEST_DIR"),
+        "part2",
+        &[Flag::value("--vocabulary").changing_answer()],
+    );
+    let lines = io.lines();
 
-    // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    // Process data, "--vocabulary <path>" reads the words from a config file
+    let number_names = match io.flag_value("--vocabulary") {
+        Some(config_path) => {
+            let config = std::fs::read_to_string(config_path)
+                .unwrap_or_else(|error| panic!("Unable to read {}: {}", config_path, error));
+            NumberNames::from_config(&config)
+        }
+        None => NumberNames::new(),
+    };
+    let output: String = match sum_calibration_values(lines, number_names) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -133,4 +308,72 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_overlapping_words() {
+        let scanner = DigitScanner::new(NumberNames::new());
+        assert_eq!(scanner.calibration_digits("eightwo"), Some((8, 2)));
+        assert_eq!(scanner.calibration_digits("zoneight"), Some((1, 8)));
+        assert_eq!(scanner.calibration_digits("xtwone3four"), Some((2, 4)));
+        assert_eq!(scanner.calibration_digits("abc"), None);
+
+        let digits: Vec<u32> = scanner
+            .find_matches("7twoneight")
+            .iter()
+            .map(|found| found.digit)
+            .collect();
+        assert_eq!(digits, vec![7, 2, 1, 8]);
+
+        // The earliest start is the first digit and the latest end the last one
+        let words = vec![(String::from("abcd"), 1), (String::from("bc"), 2)];
+        let scanner = DigitScanner::new(NumberNames::from_words(words));
+        assert_eq!(
+            scanner.find_matches("abcd"),
+            vec![
+                DigitMatch {
+                    start: 1,
+                    end: 3,
+                    digit: 2
+                },
+                DigitMatch {
+                    start: 0,
+                    end: 4,
+                    digit: 1
+                }
+            ]
+        );
+        assert_eq!(scanner.calibration_digits("abcd"), Some((1, 1)));
+        assert_eq!(scanner.calibration_digits("bcd"), Some((2, 2)));
+    }
+
+    #[test]
+    fn check_vocabularies() {
+        let config = "\
+# Spanish and roman numerals, any case
+language = spanish
+language = roman
+ignore_case = true
+ÉNNEA = 9";
+        let scanner = DigitScanner::new(NumberNames::from_config(config));
+        assert_eq!(scanner.calibration_value("xCUATROxdosx"), Some(42));
+        assert_eq!(scanner.calibration_value("année éNNEA"), Some(99));
+        assert_eq!(scanner.calibration_value("iv...viii"), Some(48));
+        assert_eq!(scanner.calibration_value("Ocho"), Some(88));
+
+        let french = NumberNames::from_words(NumberNames::language("french").unwrap());
+        let scanner = DigitScanner::new(french);
+        assert_eq!(scanner.calibration_value("zérosept"), Some(7));
+        assert_eq!(scanner.calibration_value("Zéro"), None);
+    }
+    #[test]
+    #[should_panic(expected = "Empty number name in: = 5")]
+    fn check_empty_config_word() {
+        NumberNames::from_config("language = english\n = 5");
+    }
+
+    #[test]
+    #[should_panic(expected = "Empty number name for digit 5")]
+    fn check_empty_word() {
+        NumberNames::from_words(vec![(String::from("one"), 1), (String::new(), 5)]);
+    }
 }
@@ -1,87 +1,295 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
-
-pub fn get_max_value_per_color(line_text: &str) -> (u32, u32, u32, u32) {
-    // Find index of first :
-    let index = line_text.find(':').unwrap_or(0);
-
-    let game_id: u32 = line_text[5..index].parse().unwrap_or(0);
-    let line_colors: &str = &line_text[index + 2..];
-    let line_colors: String = line_colors.replace(",", ";");
-    let colors: Vec<&str> = line_colors.split(';').collect();
-
-    let mut max_blue: u32 = 0;
-    let mut max_red: u32 = 0;
-    let mut max_green: u32 = 0;
-    for color_str in colors {
-        let color: Vec<&str> = color_str.trim().split_whitespace().collect();
-        let color_count: u32 = color[0].parse().unwrap_or(0);
-        let color_name: &str = color[1];
-        match color_name {
-            "blue" => {
-                if color_count > max_blue {
-                    max_blue = color_count;
+use std::collections::HashMap;
+use test_utils::{CliOption, Flag, SolverIo};
+
+// Any colour name found in the input
+pub type Color = String;
+
+// Number of cubes of each colour
+pub type Bag = HashMap<Color, u32>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub id: u32,
+    pub rounds: Vec<HashMap<Color, u32>>,
+}
+
+impl Game {
+    // Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+    pub fn parse(line: &str) -> Game {
+        let (header, rounds_text) = match line.split_once(':') {
+            Some(parts) => parts,
+            None => panic!("Missing ':' in game: {}", line),
+        };
+        let id: u32 = match header.trim().strip_prefix("Game ") {
+            Some(id) => id
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid game id: {}", header)),
+            None => panic!("Invalid game header: {}", header),
+        };
+
+        let rounds: Vec<HashMap<Color, u32>> = rounds_text.split(';').map(parse_cubes).collect();
+        Game { id, rounds }
+    }
+
+    // Every round can be drawn from the bag, missing colours count as 0 cubes
+    pub fn is_feasible(&self, bag: &Bag) -> bool {
+        self.rounds.iter().all(|round| {
+            round
+                .iter()
+                .all(|(color, count)| *count <= bag.get(color).copied().unwrap_or(0))
+        })
+    }
+
+    // Every colour of every round with more cubes than the bag has
+    pub fn violations(&self, bag: &Bag) -> Vec<Violation> {
+        let mut violations: Vec<Violation> = Vec::new();
+        for (round_idx, round) in self.rounds.iter().enumerate() {
+            let mut colors: Vec<&Color> = round.keys().collect();
+            colors.sort();
+            for color in colors {
+                let count = round[color];
+                let available = bag.get(color).copied().unwrap_or(0);
+                if count > available {
+                    violations.push(Violation {
+                        round: round_idx + 1,
+                        color: color.clone(),
+                        count,
+                        available,
+                    });
                 }
             }
-            "red" => {
-                if color_count > max_red {
-                    max_red = color_count;
-                }
+        }
+        violations
+    }
+
+    // Fewest cubes of each colour that make the game possible
+    pub fn minimal_bag(&self) -> Bag {
+        let mut bag: Bag = HashMap::new();
+        for round in self.rounds.iter() {
+            for (color, count) in round {
+                let max_count = bag.entry(color.clone()).or_insert(0);
+                *max_count = (*max_count).max(*count);
             }
-            "green" => {
-                if color_count > max_green {
-                    max_green = color_count;
+        }
+        bag
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    // Starting at 1, as rounds are numbered in the puzzle
+    pub round: usize,
+    pub color: Color,
+    pub count: u32,
+    pub available: u32,
+}
+
+// 3 blue, 4 red
+pub fn parse_cubes(text: &str) -> HashMap<Color, u32> {
+    let mut cubes: HashMap<Color, u32> = HashMap::new();
+    for cube in text.split(',') {
+        let mut parts = cube.split_whitespace();
+        let (count, color) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(count), Some(color), None) => (count, color),
+            _ => panic!("Invalid cubes: '{}'", cube.trim()),
+        };
+        let count: u32 = count
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid count: '{}'", count));
+        *cubes.entry(color.to_string()).or_insert(0) += count;
+    }
+    cubes
+}
+
+// Largest count of each colour over the minimal bags of the games
+pub fn minimal_bag_for<'a>(games: impl IntoIterator<Item = &'a Game>) -> Bag {
+    let mut bag: Bag = HashMap::new();
+    for game in games {
+        for (color, count) in game.minimal_bag() {
+            let max_count = bag.entry(color).or_insert(0);
+            *max_count = (*max_count).max(count);
+        }
+    }
+    bag
+}
+
+// Sorted by colour name, so the output does not depend on the map order
+pub fn format_bag(bag: &Bag) -> String {
+    let mut colors: Vec<&Color> = bag.keys().collect();
+    colors.sort();
+    let cubes: Vec<String> = colors
+        .iter()
+        .map(|color| format!("{} {}", bag[*color], color))
+        .collect();
+    cubes.join(", ")
+}
+
+// Product of the cubes of the given colours, 0 when one of them is missing.
+// None when it does not fit in a u64
+pub fn bag_power(bag: &Bag, colors: &[&str]) -> Option<u64> {
+    colors
+        .iter()
+        .map(|color| bag.get(*color).copied().unwrap_or(0) as u64)
+        .try_fold(1u64, |power, cubes| power.checked_mul(cubes))
+}
+
+pub fn process_lines<I>(lines: I) -> impl Iterator<Item = Game>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    lines.into_iter().map(|line| Game::parse(line.as_ref()))
+}
+
+pub fn bag_from(cubes: &[(&str, u32)]) -> Bag {
+    cubes
+        .iter()
+        .map(|(color, count)| (color.to_string(), *count))
+        .collect()
+}
+
+pub struct BagQuery {
+    pub bag: Bag,
+    // Games covered by the minimal bag, all of them when None
+    pub games: Option<Vec<u32>>,
+}
+
+impl Default for BagQuery {
+    fn default() -> Self {
+        BagQuery {
+            bag: bag_from(&[("red", 12), ("green", 13), ("blue", 14)]),
+            games: None,
+        }
+    }
+}
+
+impl BagQuery {
+    // "--bag <cubes>", "--bag-file <path>" and "--games <id,id,...>",
+    // the report with these options is printed with "--report"
+    pub const FLAGS: &'static [Flag] = &[
+        Flag::value("--bag").changing_answer(),
+        Flag::value("--bag-file").changing_answer(),
+        Flag::value("--games"),
+    ];
+
+    pub fn from_options(options: &[CliOption]) -> BagQuery {
+        let mut query = BagQuery::default();
+        for option in options {
+            let value: &str = &option.value;
+            match option.flag.name {
+                "--bag" => query.bag = parse_cubes(value),
+                "--bag-file" => {
+                    let content = std::fs::read_to_string(value)
+                        .unwrap_or_else(|error| panic!("Unable to read {}: {}", value, error));
+                    // One colour per line or comma separated
+                    let cubes: Vec<&str> = content
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .collect();
+                    query.bag = parse_cubes(&cubes.join(","));
                 }
+                "--games" => {
+                    let ids: Vec<u32> = value
+                        .split(',')
+                        .map(|id| {
+                            id.trim()
+                                .parse()
+                                .unwrap_or_else(|_| panic!("Invalid game id: {}", id))
+                        })
+                        .collect();
+                    query.games = Some(ids);
+                }
+                _ => (),
             }
-            _ => {
-                println!("Invalid color: {}", color_name);
-                break;
-            }
-        };
+        }
+        query
     }
-    return (game_id, max_blue, max_red, max_green);
 }
 
-fn advent_of_code(input_text: &str) -> String {
-    let mut games_available: Vec<u32> = Vec::new();
-
-    let max_blue_expected: u32 = 14;
-    let max_red_expected: u32 = 12;
-    let max_green_expected: u32 = 13;
-
-    for line in input_text.lines() {
-        let (game_id, max_blue, max_red, max_green) = get_max_value_per_color(line);
-        if max_blue <= max_blue_expected
-            && max_red <= max_red_expected
-            && max_green <= max_green_expected
-        {
-            games_available.push(game_id);
+pub fn sum_feasible_ids(games: impl IntoIterator<Item = Game>, bag: &Bag) -> Result<u64, String> {
+    games
+        .into_iter()
+        .filter(|game| game.is_feasible(bag))
+        .try_fold(0u64, |sum, game| sum.checked_add(game.id as u64))
+        .ok_or_else(|| String::from("Sum of feasible game ids too large"))
+}
+
+pub fn bag_report(games: &[Game], query: &BagQuery) -> String {
+    let mut report: Vec<String> = Vec::new();
+    report.push(format!("Bag: {}", format_bag(&query.bag)));
+
+    let feasible: Vec<String> = games
+        .iter()
+        .filter(|game| game.is_feasible(&query.bag))
+        .map(|game| game.id.to_string())
+        .collect();
+    report.push(format!("Feasible games: {}", feasible.join(", ")));
+
+    for game in games {
+        for violation in game.violations(&query.bag) {
+            report.push(format!(
+                "Game {}: round {} has {} {}, the bag has {}",
+                game.id, violation.round, violation.count, violation.color, violation.available
+            ));
         }
     }
 
-    // Sum all id in games_available
-    let mut sum: u32 = 0;
-    for game_id in games_available {
-        sum += game_id;
+    let subset: Vec<&Game> = match &query.games {
+        Some(ids) => ids
+            .iter()
+            .map(|id| match games.iter().find(|game| game.id == *id) {
+                Some(game) => game,
+                None => panic!("Unknown game: {}", id),
+            })
+            .collect(),
+        None => games.iter().collect(),
+    };
+    let ids: Vec<String> = subset.iter().map(|game| game.id.to_string()).collect();
+    report.push(format!(
+        "Minimal bag for games {}: {}",
+        ids.join(", "),
+        format_bag(&minimal_bag_for(subset))
+    ));
+    report.join("\n")
+}
+
+#[cfg_attr(not(test), allow(unused))]
+fn advent_of_code(input_text: &str) -> String {
+    let games = process_lines(input_text.lines());
+    match sum_feasible_ids(games, &BagQuery::default().bag) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => panic!("{}", error),
     }
-    return sum.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Stream input lines from a path argument, stdin or the txt file
+    let flags: Vec<Flag> = [BagQuery::FLAGS, &[Flag::switch("--report")]].concat();
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part1", &flags);
+    let lines = io.lines();
 
     // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    let query = BagQuery::from_options(&io.options);
+    // The report needs every game at once, so the lines are only kept with "--report"
+    let games: Box<dyn Iterator<Item = Game>> = if io.has_flag("--report") {
+        let games: Vec<Game> = process_lines(lines).collect();
+        println!("{}", bag_report(&games, &query));
+        Box::new(games.into_iter())
+    } else {
+        Box::new(process_lines(lines))
+    };
+    let output: String = match sum_feasible_ids(games, &query.bag) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -98,4 +306,69 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_game_model() {
+        let game = Game::parse("Game 12: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green, 1 teal");
+        assert_eq!(game.id, 12);
+        assert_eq!(game.rounds.len(), 3);
+        assert_eq!(game.rounds[2]["teal"], 1);
+
+        let minimal_bag = game.minimal_bag();
+        assert_eq!(
+            minimal_bag,
+            bag_from(&[("blue", 6), ("red", 4), ("green", 2), ("teal", 1)])
+        );
+        assert_eq!(bag_power(&minimal_bag, &["red", "green", "blue"]), Some(48));
+        assert_eq!(bag_power(&minimal_bag, &["red", "purple"]), Some(0));
+
+        assert!(game.is_feasible(&minimal_bag));
+        assert!(!game.is_feasible(&bag_from(&[("red", 12), ("green", 13), ("blue", 14)])));
+    }
+
+    #[test]
+    fn check_bag_report() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let games: Vec<Game> = process_lines(input.lines()).collect();
+
+        let args: Vec<String> = [
+            "part1",
+            "--bag",
+            "12 red, 13 green, 14 blue",
+            "--games",
+            "1,2",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let io = SolverIo::from_args(&args, "", "part1", BagQuery::FLAGS).unwrap();
+        let query = BagQuery::from_options(&io.options);
+        let report = bag_report(&games, &query);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "Bag: 14 blue, 13 green, 12 red");
+        assert_eq!(lines[1], "Feasible games: 1, 2, 5");
+        assert_eq!(lines[2], "Game 3: round 1 has 20 red, the bag has 12");
+        assert_eq!(lines[3], "Game 4: round 3 has 15 blue, the bag has 14");
+        assert_eq!(lines[4], "Game 4: round 3 has 14 red, the bag has 12");
+        assert_eq!(
+            lines[5],
+            "Minimal bag for games 1, 2: 6 blue, 3 green, 4 red"
+        );
+        assert_eq!(lines.len(), 6);
+    }
+    #[test]
+    fn check_large_sums() {
+        // Game ids and powers past u32 still add up
+        let input = "Game 4000000000: 1 red\nGame 4000000001: 2 blue";
+        assert_eq!(advent_of_code(input), "8000000001");
+
+        let minimal_bag =
+            Game::parse("Game 1: 4294967295 red, 4294967295 green, 2 blue").minimal_bag();
+        assert_eq!(
+            bag_power(&minimal_bag, &["red", "green"]),
+            Some(18446744065119617025)
+        );
+        assert_eq!(bag_power(&minimal_bag, &["red", "green", "blue"]), None);
+    }
 }
@@ -1,30 +1,46 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 mod part1;
 
+fn sum_powers<I>(lines: I) -> Result<u64, String>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let mut sum: u64 = 0;
+    for game in part1::process_lines(lines) {
+        let power = part1::bag_power(&game.minimal_bag(), &["red", "green", "blue"]);
+        sum = match power.and_then(|power| sum.checked_add(power)) {
+            Some(sum) => sum,
+            None => return Err(format!("Sum of powers too large at game {}", game.id)),
+        };
+    }
+    return Ok(sum);
+}
+
+#[cfg_attr(not(test), allow(unused))]
 fn advent_of_code(input_text: &str) -> String {
-    let mut sum: u32 = 0;
-    for line in input_text.lines() {
-        let (_, max_blue, max_red, max_green) = part1::get_max_value_per_color(line);
-        sum += max_blue * max_red * max_green;
+    match sum_powers(input_text.lines()) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => panic!("{}", error),
     }
-    return sum.to_string();
 }
 
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Stream input lines from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part2", &[]);
+    let lines = io.lines();
 
     // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    let output: String = match sum_powers(lines) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
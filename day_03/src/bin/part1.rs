@@ -1,163 +1,193 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use lib_utils::Matrix;
+use test_utils::SolverIo;
 
-struct GenerateCandidates<'a> {
-    candidates: Vec<bool>,
-    prev_line: &'a str,
-    current_line: &'a str,
-    next_line: &'a str,
+// Number in a row, from the start col to the end col (exclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberSpan {
+    pub row: usize,
+    pub start: usize,
+    pub end: usize,
+    pub value: u32,
 }
 
-impl<'a> GenerateCandidates<'a> {
-    fn new(prev_line: &'a str, current_line: &'a str, next_line: &'a str) -> Self {
-        let mut instance: GenerateCandidates<'a> = GenerateCandidates {
-            candidates: vec![false; current_line.len()],
-            prev_line,
-            current_line,
-            next_line,
-        };
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub row: usize,
+    pub col: usize,
+    pub character: char,
+}
 
-        instance.check_candidates();
-        instance
-    }
+// Cells around a symbol that can touch a number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Adjacency {
+    // The 8 cells around
+    #[default]
+    Diagonal,
+    // Only N, S, W and E
+    Orthogonal,
+}
 
-    fn check_candidates(&mut self) {
-        for eval_line in vec![self.prev_line, self.current_line, self.next_line] {
-            for (i, c) in eval_line.chars().enumerate() {
-                if c.is_numeric() || c == '.' || c == '\n' {
-                    continue;
-                }
-                if i > 0 {
-                    self.candidates[i - 1] = true;
-                }
-                self.candidates[i] = true;
-                if i < self.candidates.len() - 1 {
-                    self.candidates[i + 1] = true;
-                }
-            }
+impl Adjacency {
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Adjacency::Diagonal => &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ],
+            Adjacency::Orthogonal => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
         }
     }
-
-    fn get_candidates(&self) -> Vec<bool> {
-        self.candidates.clone()
-    }
 }
 
-pub struct NumberFromCandidates<'a> {
-    candidates: Vec<bool>,
-    eval_line: &'a str,
+pub struct Schematic {
+    pub adjacency: Adjacency,
+    pub numbers: Vec<NumberSpan>,
+    pub symbols: Vec<Symbol>,
+    // Index of the number that covers each cell
+    pub number_at: Matrix<Option<usize>>,
+    // Indexes of the numbers around each symbol, and of the symbols around each number
+    pub numbers_around: Vec<Vec<usize>>,
+    pub symbols_around: Vec<Vec<usize>>,
 }
 
-impl NumberFromCandidates<'_> {
-    pub fn new<'a>(candidates: Vec<bool>, eval_line: &'a str) -> NumberFromCandidates<'a> {
-        return NumberFromCandidates {
-            candidates,
-            eval_line,
-        };
+impl Schematic {
+    pub fn new(input: &str) -> Schematic {
+        Schematic::with_adjacency(input, Adjacency::default())
     }
 
-    fn get_number_at(&mut self, index: usize) -> u32 {
-        let mut number_name: String = String::new();
+    pub fn with_adjacency(input: &str, adjacency: Adjacency) -> Schematic {
+        let num_lines: usize = input.lines().count();
+        let len_line: usize = input.lines().next().unwrap().chars().count();
+        let mut number_at: Matrix<Option<usize>> = Matrix::new(num_lines, len_line, None);
+        let mut numbers: Vec<NumberSpan> = Vec::new();
+        let mut symbols: Vec<Symbol> = Vec::new();
 
-        // Search left
-        for i in (0..index).rev() {
-            if !self.eval_line.chars().nth(i).unwrap().is_numeric() {
-                break;
+        for (row, line) in input.lines().enumerate() {
+            let mut current: Option<NumberSpan> = None;
+            for (col, character) in line.chars().enumerate() {
+                if let Some(digit) = character.to_digit(10) {
+                    let number = current.get_or_insert(NumberSpan {
+                        row,
+                        start: col,
+                        end: col,
+                        value: 0,
+                    });
+                    number.end = col + 1;
+                    number.value = number
+                        .value
+                        .checked_mul(10)
+                        .and_then(|value| value.checked_add(digit))
+                        .unwrap_or_else(|| {
+                            panic!("Number too large at ({}, {})", row, number.start)
+                        });
+                    number_at.set_value(Some(numbers.len()), row, col);
+                    continue;
+                }
+                if let Some(number) = current.take() {
+                    numbers.push(number);
+                }
+                if character != '.' {
+                    symbols.push(Symbol {
+                        row,
+                        col,
+                        character,
+                    });
+                }
             }
-            number_name = self.eval_line.chars().nth(i).unwrap().to_string() + &number_name;
-            self.candidates[i] = false;
-        }
-        // Search right
-        for i in index..self.eval_line.len() {
-            if !self.eval_line.chars().nth(i).unwrap().is_numeric() {
-                break;
+            if let Some(number) = current.take() {
+                numbers.push(number);
             }
-            number_name.push(self.eval_line.chars().nth(i).unwrap());
-            self.candidates[i] = false;
         }
 
-        number_name = number_name.chars().collect();
-        return number_name.parse().unwrap();
+        let mut schematic = Schematic {
+            adjacency,
+            numbers,
+            symbols,
+            number_at,
+            numbers_around: Vec::new(),
+            symbols_around: Vec::new(),
+        };
+        schematic.build_adjacency();
+        schematic
     }
 
-    pub fn get_numbers_in_candidates(&mut self) -> Vec<u32> {
-        let mut numbers: Vec<u32> = Vec::new();
-
-        for i in 0..self.candidates.len() {
-            if self.candidates[i] {
-                let c: char = self.eval_line.chars().nth(i).unwrap();
-                if c.is_numeric() {
-                    let number: u32 = self.get_number_at(i);
-                    numbers.push(number);
+    // Numbers in the cells around each symbol, each number only once
+    fn build_adjacency(&mut self) {
+        self.numbers_around = vec![Vec::new(); self.symbols.len()];
+        self.symbols_around = vec![Vec::new(); self.numbers.len()];
+        for (symbol_idx, symbol) in self.symbols.iter().enumerate() {
+            for (row_offset, col_offset) in self.adjacency.offsets() {
+                let row = symbol.row.checked_add_signed(*row_offset);
+                let col = symbol.col.checked_add_signed(*col_offset);
+                let (row, col) = match (row, col) {
+                    (Some(row), Some(col))
+                        if row < self.number_at.rows && col < self.number_at.cols =>
+                    {
+                        (row, col)
+                    }
+                    _ => continue,
+                };
+                if let Some(number_idx) = *self.number_at.at(row, col) {
+                    if !self.numbers_around[symbol_idx].contains(&number_idx) {
+                        self.numbers_around[symbol_idx].push(number_idx);
+                        self.symbols_around[number_idx].push(symbol_idx);
+                    }
                 }
             }
         }
-        return numbers;
     }
 
-    pub fn get_numbers_sum(&mut self) -> u32 {
-        let numbers: Vec<u32> = self.get_numbers_in_candidates();
-        let mut sum: u32 = 0;
-        for number in numbers {
-            sum += number;
-        }
-        return sum;
+    // Numbers next to at least one symbol
+    pub fn part_numbers(&self) -> impl Iterator<Item = &NumberSpan> {
+        self.numbers
+            .iter()
+            .zip(self.symbols_around.iter())
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(number, _)| number)
     }
-}
 
-fn init_line(line_len: usize, line_char: char) -> String {
-    let mut line: String = String::new();
-    for _ in 0..line_len {
-        line.push(line_char);
+    // Symbols with exactly n numbers around, along with those numbers
+    pub fn symbols_with_numbers(
+        &self,
+        n: usize,
+    ) -> impl Iterator<Item = (&Symbol, Vec<&NumberSpan>)> {
+        self.symbols
+            .iter()
+            .zip(self.numbers_around.iter())
+            .filter(move |(_, numbers)| numbers.len() == n)
+            .map(|(symbol, numbers)| {
+                let numbers = numbers.iter().map(|idx| &self.numbers[*idx]).collect();
+                (symbol, numbers)
+            })
     }
-    return line;
 }
 
 fn advent_of_code(input_text: &str) -> String {
-    let mut current_line: &str = "";
-    let mut prev_line: &str = "";
-    let mut next_line: &str = "";
-
-    let aux_line: String = init_line(input_text.lines().nth(0).unwrap().len(), '.');
-
-    let mut sum: u32 = 0;
-    for i in 0..input_text.lines().count() {
-        current_line = input_text.lines().nth(i).unwrap();
-        if i == 0 {
-            prev_line = &aux_line;
-        } else {
-            prev_line = input_text.lines().nth(i - 1).unwrap();
-        }
-
-        if i == input_text.lines().count() - 1 {
-            next_line = &aux_line;
-        } else {
-            next_line = input_text.lines().nth(i + 1).unwrap();
-        }
-        let candidates: Vec<bool> =
-            GenerateCandidates::new(&prev_line, &current_line, &next_line).get_candidates();
-        let mut number_from_candidates: NumberFromCandidates =
-            NumberFromCandidates::new(candidates, current_line);
-        sum += number_from_candidates.get_numbers_sum();
-    }
+    let schematic: Schematic = Schematic::new(input_text);
+    let sum: u64 = schematic
+        .part_numbers()
+        .map(|number| number.value as u64)
+        .sum();
     return sum.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part1", &[]);
+    let input: String = io.content();
 
     // Process data
     let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -174,4 +204,60 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_schematic() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let schematic = Schematic::new(&input);
+
+        assert_eq!(schematic.numbers.len(), 10);
+        assert_eq!(schematic.symbols.len(), 6);
+        assert_eq!(
+            schematic.numbers[1],
+            NumberSpan {
+                row: 0,
+                start: 5,
+                end: 8,
+                value: 114
+            }
+        );
+        assert_eq!(*schematic.number_at.at(9, 3), Some(8));
+
+        let not_parts: Vec<u32> = schematic
+            .numbers
+            .iter()
+            .zip(schematic.symbols_around.iter())
+            .filter(|(_, symbols)| symbols.is_empty())
+            .map(|(number, _)| number.value)
+            .collect();
+        assert_eq!(not_parts, vec![114, 58]);
+
+        let single: Vec<char> = schematic
+            .symbols_with_numbers(1)
+            .map(|(symbol, _)| symbol.character)
+            .collect();
+        assert_eq!(single, vec!['#', '*', '+', '$']);
+        let pairs: Vec<Vec<u32>> = schematic
+            .symbols_with_numbers(2)
+            .map(|(_, numbers)| numbers.iter().map(|number| number.value).collect())
+            .collect();
+        assert_eq!(pairs, vec![vec![467, 35], vec![755, 598]]);
+
+        // Cols are counted in chars, also for symbols outside ASCII
+        let schematic = Schematic::new("é12\n..*");
+        assert_eq!(schematic.numbers[0].start, 1);
+        assert_eq!(schematic.symbols_around[0], vec![0, 1]);
+    }
+
+    #[test]
+    fn check_large_part_numbers() {
+        assert_eq!(advent_of_code("4000000000*4000000000"), "8000000000");
+    }
+
+    #[test]
+    #[should_panic(expected = "Number too large at (0, 1)")]
+    fn check_number_overflow() {
+        Schematic::new(".4294967296*");
+    }
 }
@@ -1,117 +1,175 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use std::fmt;
+use test_utils::{CliOption, Flag, SolverIo};
 mod part1;
+use part1::{Adjacency, Schematic, Symbol};
 
-struct Gear<'a> {
-    candidates: Vec<bool>,
-    line_prev: &'a str,
-    line_current: &'a str,
-    line_next: &'a str,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregation {
+    Product,
+    Sum,
+    Max,
 }
 
-impl<'a> Gear<'a> {
-    fn new(line_prev: &'a str, line_current: &'a str, line_next: &'a str) -> Gear<'a> {
-        Gear {
-            candidates: vec![false; line_current.len()],
-            line_prev: line_prev,
-            line_current: line_current,
-            line_next: line_next,
+impl Aggregation {
+    // None when the value does not fit in a u64
+    fn apply(&self, numbers: &[u32]) -> Option<u64> {
+        let mut values = numbers.iter().map(|number| *number as u64);
+        match self {
+            Aggregation::Product => {
+                values.try_fold(1u64, |product, value| product.checked_mul(value))
+            }
+            Aggregation::Sum => values.try_fold(0u64, |sum, value| sum.checked_add(value)),
+            Aggregation::Max => Some(values.max().unwrap_or(0)),
         }
     }
+}
 
-    fn find_numbers(&mut self) -> Vec<u32> {
-        let mut numbers: Vec<u32> = Vec::new();
-        for eval_line in vec![self.line_prev, self.line_current, self.line_next] {
-            let mut numbers_from_candidates: part1::NumberFromCandidates =
-                part1::NumberFromCandidates::new(self.candidates.clone(), eval_line);
-            numbers.append(&mut numbers_from_candidates.get_numbers_in_candidates());
+// A gear is any of the symbols with exactly `adjacent` numbers around
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GearRule {
+    symbols: Vec<char>,
+    adjacent: usize,
+    aggregation: Aggregation,
+    adjacency: Adjacency,
+}
+
+impl Default for GearRule {
+    fn default() -> Self {
+        GearRule {
+            symbols: vec!['*'],
+            adjacent: 2,
+            aggregation: Aggregation::Product,
+            adjacency: Adjacency::Diagonal,
         }
-        return numbers;
     }
+}
 
-    fn evaluate_gear(&mut self, index: usize) -> u32 {
-        if index > 0 {
-            self.candidates[index - 1] = true;
-        }
-        self.candidates[index] = true;
-        if index < self.candidates.len() - 1 {
-            self.candidates[index + 1] = true;
-        }
+impl GearRule {
+    // "--symbols <chars>", "--adjacent <n>", "--aggregation <product|sum|max>"
+    // and "--orthogonal"
+    const FLAGS: &'static [Flag] = &[
+        Flag::value("--symbols").changing_answer(),
+        Flag::value("--adjacent").changing_answer(),
+        Flag::value("--aggregation").changing_answer(),
+        Flag::switch("--orthogonal").changing_answer(),
+    ];
 
-        let numbers: Vec<u32> = self.find_numbers();
-        let mut gear_ratio: u32 = 0;
-        if numbers.len() == 2 {
-            gear_ratio = numbers[0] * numbers[1];
+    fn from_options(options: &[CliOption]) -> GearRule {
+        let mut rule = GearRule::default();
+        for option in options {
+            let value: &str = &option.value;
+            match option.flag.name {
+                "--symbols" => rule.symbols = value.chars().collect(),
+                "--adjacent" => {
+                    rule.adjacent = value
+                        .parse::<usize>()
+                        .unwrap_or_else(|_| panic!("Invalid adjacent count: {}", value));
+                }
+                "--aggregation" => {
+                    rule.aggregation = match value {
+                        "product" => Aggregation::Product,
+                        "sum" => Aggregation::Sum,
+                        "max" => Aggregation::Max,
+                        _ => panic!("Invalid aggregation: {}", value),
+                    };
+                }
+                "--orthogonal" => rule.adjacency = Adjacency::Orthogonal,
+                _ => (),
+            }
         }
+        rule
+    }
 
-        self.candidates = vec![false; self.line_current.len()];
-        return gear_ratio;
+    fn evaluate(&self, input: &str) -> Result<Vec<GearReport>, String> {
+        let schematic: Schematic = Schematic::with_adjacency(input, self.adjacency);
+        schematic
+            .symbols_with_numbers(self.adjacent)
+            .filter(|(symbol, _)| self.symbols.contains(&symbol.character))
+            .map(|(symbol, numbers)| {
+                let numbers: Vec<u32> = numbers.iter().map(|number| number.value).collect();
+                match self.aggregation.apply(&numbers) {
+                    Some(value) => Ok(GearReport {
+                        symbol: *symbol,
+                        numbers,
+                        value,
+                    }),
+                    None => Err(format!(
+                        "Gear value too large at ({}, {})",
+                        symbol.row, symbol.col
+                    )),
+                }
+            })
+            .collect()
     }
+}
 
-    fn get_gear_ratios(&mut self) -> u32 {
-        let mut gear_ratios: Vec<u32> = Vec::new();
-        for (i, c) in self.line_current.chars().enumerate() {
-            if c != '*' {
-                continue;
-            }
+struct GearReport {
+    symbol: Symbol,
+    numbers: Vec<u32>,
+    value: u64,
+}
 
-            let gear_ratio: u32 = self.evaluate_gear(i);
-            gear_ratios.push(gear_ratio);
-        }
-        return gear_ratios.iter().sum();
+impl fmt::Display for GearReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let numbers: Vec<String> = self
+            .numbers
+            .iter()
+            .map(|number| number.to_string())
+            .collect();
+        write!(
+            f,
+            "{} at ({}, {}): {} -> {}",
+            self.symbol.character,
+            self.symbol.row,
+            self.symbol.col,
+            numbers.join(", "),
+            self.value
+        )
     }
 }
 
-fn init_line(line_len: usize, line_char: char) -> String {
-    let mut line: String = String::new();
-    for _ in 0..line_len {
-        line.push(line_char);
-    }
-    return line;
+fn sum_gears(reports: &[GearReport]) -> Result<u64, String> {
+    reports
+        .iter()
+        .try_fold(0u64, |sum, report| sum.checked_add(report.value))
+        .ok_or_else(|| String::from("Sum of gears too large"))
 }
 
+#[cfg_attr(not(test), allow(unused))]
 fn advent_of_code(input_text: &str) -> String {
-    let mut current_line: &str = "";
-    let mut prev_line: &str = "";
-    let mut next_line: &str = "";
-
-    let aux_line: String = init_line(input_text.lines().nth(0).unwrap().len(), '.');
-
-    let mut sum: u32 = 0;
-    for i in 0..input_text.lines().count() {
-        current_line = input_text.lines().nth(i).unwrap();
-        if i == 0 {
-            prev_line = &aux_line;
-        } else {
-            prev_line = input_text.lines().nth(i - 1).unwrap();
-        }
-
-        if i == input_text.lines().count() - 1 {
-            next_line = &aux_line;
-        } else {
-            next_line = input_text.lines().nth(i + 1).unwrap();
-        }
-        let mut gear: Gear = Gear::new(prev_line, current_line, next_line);
-        sum += gear.get_gear_ratios();
+    match GearRule::default()
+        .evaluate(input_text)
+        .and_then(|reports| sum_gears(&reports))
+    {
+        Ok(sum) => sum.to_string(),
+        Err(error) => panic!("{}", error),
     }
-    return sum.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part2", GearRule::FLAGS);
+    let input: String = io.content();
 
     // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    let rule = GearRule::from_options(&io.options);
+    let reports = rule.evaluate(&input).and_then(|reports| {
+        for report in reports.iter() {
+            eprintln!("{}", report);
+        }
+        sum_gears(&reports)
+    });
+    let output: String = match reports {
+        Ok(sum) => sum.to_string(),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -128,4 +186,78 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_gear_rules() {
+        let input_test_path: &str = &get_full_path("src/bin/part2/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+
+        let reports = GearRule::default().evaluate(&input).unwrap();
+        let lines: Vec<String> = reports.iter().map(|report| report.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "* at (1, 3): 467, 35 -> 16345",
+                "* at (8, 5): 755, 598 -> 451490"
+            ]
+        );
+
+        // Every symbol next to a single number
+        let args: Vec<String> = [
+            "part2",
+            "--symbols",
+            "*#+$",
+            "--adjacent",
+            "1",
+            "--aggregation",
+            "sum",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let io = SolverIo::from_args(&args, "", "part2", GearRule::FLAGS).unwrap();
+        let rule = GearRule::from_options(&io.options);
+        let reports = rule.evaluate(&input).unwrap();
+        assert_eq!(sum_gears(&reports), Ok(633 + 617 + 592 + 664));
+
+        // Without diagonals 467 and 755 are no longer next to a '*' and '+' has no numbers
+        let rule = GearRule {
+            adjacency: Adjacency::Orthogonal,
+            ..rule
+        };
+        let reports = rule.evaluate(&input).unwrap();
+        let values: Vec<u64> = reports.iter().map(|report| report.value).collect();
+        assert_eq!(values, vec![35, 633, 617, 664, 598]);
+
+        let rule = GearRule {
+            adjacent: 2,
+            aggregation: Aggregation::Max,
+            ..GearRule::default()
+        };
+        let reports = rule.evaluate(&input).unwrap();
+        assert_eq!(sum_gears(&reports), Ok(467 + 755));
+    }
+
+    #[test]
+    fn check_gear_overflow() {
+        // Six numbers of 9999 multiply past u64
+        let rule = GearRule {
+            adjacent: 6,
+            ..GearRule::default()
+        };
+        assert_eq!(
+            rule.evaluate("9999.9999\n9999*9999\n9999.9999").err(),
+            Some(String::from("Gear value too large at (1, 4)"))
+        );
+
+        // Each gear fits in u64, their sum does not
+        let reports = GearRule::default()
+            .evaluate("4000000000*4000000000.4000000000*4000000000")
+            .unwrap();
+        assert_eq!(reports[0].value, 16000000000000000000);
+        assert_eq!(
+            sum_gears(&reports),
+            Err(String::from("Sum of gears too large"))
+        );
+    }
 }
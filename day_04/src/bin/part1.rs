@@ -1,76 +1,221 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use std::collections::HashSet;
+use test_utils::{Flag, SolverIo};
 
-pub fn get_scratchcards_matches(winning_numbers: &Vec<u32>, scratch_result: &Vec<u32>) -> u32 {
-    let mut matches: u32 = 0;
+// Set of numbers, one bit per number below MAX_BITSET_NUMBER. The bitset grows up to the
+// largest number inserted, so larger numbers go to a HashSet instead of allocating
+// up to 512 MB of bits
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NumberSet {
+    words: Vec<u64>,
+    large: HashSet<u32>,
+}
+
+impl NumberSet {
+    // The bitset takes at most 8 KiB
+    pub const MAX_BITSET_NUMBER: u32 = 1 << 16;
 
-    // Get numbers that match in both arrays
-    for scratch_number in scratch_result {
-        if winning_numbers.contains(scratch_number) {
-            matches += 1;
+    pub fn new() -> NumberSet {
+        NumberSet::default()
+    }
+
+    // False when the number was already in the set
+    pub fn insert(&mut self, number: u32) -> bool {
+        if number >= NumberSet::MAX_BITSET_NUMBER {
+            return self.large.insert(number);
+        }
+        let (word, bit) = (number as usize / 64, number % 64);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
         }
+        let is_new = self.words[word] & (1 << bit) == 0;
+        self.words[word] |= 1 << bit;
+        is_new
     }
-    return matches;
+
+    pub fn intersection_len(&self, other: &NumberSet) -> u32 {
+        let small: u32 = self
+            .words
+            .iter()
+            .zip(other.words.iter())
+            .map(|(a, b)| (a & b).count_ones())
+            .sum();
+        small + self.large.intersection(&other.large).count() as u32
+    }
+}
+
+impl FromIterator<u32> for NumberSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(numbers: I) -> Self {
+        let mut set = NumberSet::new();
+        for number in numbers {
+            set.insert(number);
+        }
+        set
+    }
+}
+
+// Numbers found more than once, in the order of their second appearance
+pub fn find_duplicates(numbers: &[u32]) -> Vec<u32> {
+    let mut seen = NumberSet::new();
+    let mut reported = NumberSet::new();
+    let mut duplicates: Vec<u32> = Vec::new();
+    for number in numbers {
+        if !seen.insert(*number) && reported.insert(*number) {
+            duplicates.push(*number);
+        }
+    }
+    duplicates
+}
+
+// Numbers on both sides, a repeated number only counts once
+pub fn get_scratchcards_matches(winning_numbers: &[u32], scratch_result: &[u32]) -> u32 {
+    let winning: NumberSet = winning_numbers.iter().copied().collect();
+    let scratch: NumberSet = scratch_result.iter().copied().collect();
+    winning.intersection_len(&scratch)
 }
 
 pub fn get_scratchcards_numbers(line_text: &str) -> (Vec<u32>, Vec<u32>) {
     // Ej: Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
 
-    // Find indexes
-    let start_index = line_text.find(':').unwrap_or(0);
-    let pipe_index = line_text.find('|').unwrap_or(0);
-    // let card_id: u32 = line_text[5..start_index].parse().unwrap_or(0);
-    let winning_numbers_str: &str = &line_text[start_index + 1..pipe_index];
-    let scratch_result_str: &str = &line_text[pipe_index + 1..];
+    // Split the numbers after the header by the pipe
+    let start_index = line_text.find(':').map_or(0, |index| index + 1);
+    let (winning_numbers_str, scratch_result_str) = match line_text[start_index..].split_once('|') {
+        Some(sides) => sides,
+        None => panic!("Missing '|' in card: {}", line_text),
+    };
 
-    let winning_numbers: Vec<u32> = winning_numbers_str
-        .split_whitespace() // Split by whitespace and return an iterator
-        .filter_map(|s| s.parse().ok()) // Iterate over the iterator, parse each element, and filter out failures
-        .collect(); // Collect the results into a new Vec
-
-    let scratch_result: Vec<u32> = scratch_result_str
-        .split_whitespace() // Split by whitespace and return an iterator
-        .map(|s| s.parse().unwrap_or(0)) // Iterate over the iterator, parse each element, and filter out failures
-        .collect(); // Collect the results into a new Vec
+    // Every token is a number, a typo would silently change the matches
+    let parse_numbers = |numbers_str: &str| -> Vec<u32> {
+        numbers_str
+            .split_whitespace()
+            .map(|s| {
+                s.parse()
+                    .unwrap_or_else(|_| panic!("Invalid number {} in card: {}", s, line_text))
+            })
+            .collect()
+    };
+    let winning_numbers: Vec<u32> = parse_numbers(winning_numbers_str);
+    let scratch_result: Vec<u32> = parse_numbers(scratch_result_str);
 
     return (winning_numbers, scratch_result);
 }
 
-fn get_card_macthes_points(matches: u32) -> u32 {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scratchcard {
+    pub id: u32,
+    pub winning_numbers: Vec<u32>,
+    pub numbers: Vec<u32>,
+}
+
+impl Scratchcard {
+    pub fn parse(line_text: &str) -> Scratchcard {
+        let header: &str = match line_text.split_once(':') {
+            Some((header, _)) => header,
+            None => panic!("Missing ':' in card: {}", line_text),
+        };
+        let id: u32 = match header.strip_prefix("Card") {
+            Some(id) => id
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid card id: {}", header)),
+            None => panic!("Invalid card header: {}", header),
+        };
+        let (winning_numbers, numbers) = get_scratchcards_numbers(line_text);
+        Scratchcard {
+            id,
+            winning_numbers,
+            numbers,
+        }
+    }
+
+    pub fn matches(&self) -> u32 {
+        get_scratchcards_matches(&self.winning_numbers, &self.numbers)
+    }
+
+    // None when the points do not fit in a u64
+    pub fn points(&self) -> Option<u64> {
+        get_card_macthes_points(self.matches())
+    }
+
+    // Duplicated numbers would count once, but they are likely a broken card
+    pub fn validate(&self) -> Result<(), String> {
+        let winning = find_duplicates(&self.winning_numbers);
+        let numbers = find_duplicates(&self.numbers);
+        if winning.is_empty() && numbers.is_empty() {
+            return Ok(());
+        }
+        Err(format!(
+            "Card {}: duplicated winning numbers {:?}, duplicated numbers {:?}",
+            self.id, winning, numbers
+        ))
+    }
+}
+
+pub fn process_lines<I>(lines: I) -> impl Iterator<Item = Scratchcard>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    lines
+        .into_iter()
+        .map(|line| Scratchcard::parse(line.as_ref()))
+}
+
+fn get_card_macthes_points(matches: u32) -> Option<u64> {
     if matches == 0 {
-        return 0;
+        return Some(0);
     }
-    let points: u32 = 2u32.pow(matches - 1);
+    let points: Option<u64> = 2u64.checked_pow(matches - 1);
     return points;
 }
 
-fn advent_of_code(input_text: &str) -> String {
-    let mut scratch_points: u32 = 0;
+fn sum_points(cards: impl IntoIterator<Item = Scratchcard>) -> Result<u64, String> {
+    let mut scratch_points: u64 = 0;
 
-    for line in input_text.lines() {
-        let (winning_numbers, scratch_result) = get_scratchcards_numbers(line);
-        let matches: u32 = get_scratchcards_matches(&winning_numbers, &scratch_result);
-        let card_points: u32 = get_card_macthes_points(matches);
-        scratch_points += card_points;
+    for card in cards {
+        let points = card.points();
+        scratch_points = match points.and_then(|points| scratch_points.checked_add(points)) {
+            Some(points) => points,
+            None => return Err(format!("Sum of points too large at card {}", card.id)),
+        };
     }
-    return scratch_points.to_string();
+    return Ok(scratch_points);
 }
 
 #[cfg_attr(not(test), allow(unused))]
-fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
+fn advent_of_code(input_text: &str) -> String {
+    match sum_points(process_lines(input_text.lines())) {
+        Ok(points) => points.to_string(),
+        Err(error) => panic!("{}", error),
     }
+}
 
-    // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+#[cfg_attr(not(test), allow(unused))]
+fn main() {
+    // Stream input lines from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(
+        env!("CARGO_MANIFEST_DIR"),
+        "part1",
+        &[Flag::switch("--validate")],
+    );
+    let lines = io.lines();
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Process data, "--validate" reports the cards with duplicated numbers
+    let validate: bool = io.has_flag("--validate");
+    let cards = process_lines(lines).inspect(|card| {
+        if let (true, Err(error)) = (validate, card.validate()) {
+            eprintln!("{}", error);
+        }
+    });
+    let output: String = match sum_points(cards) {
+        Ok(points) => points.to_string(),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -87,4 +232,80 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_number_set() {
+        let set: NumberSet = [3, 64, 99, 3].into_iter().collect();
+        let other: NumberSet = [64, 65, 3, 1000].into_iter().collect();
+        assert_eq!(set.intersection_len(&other), 2);
+        assert_eq!(other.intersection_len(&set), 2);
+
+        // Numbers past the bitset don't grow it
+        let large: NumberSet = [u32::MAX, 7, u32::MAX, NumberSet::MAX_BITSET_NUMBER]
+            .into_iter()
+            .collect();
+        assert_eq!(large.words.len(), 1);
+        let other: NumberSet = [NumberSet::MAX_BITSET_NUMBER, 7, u32::MAX - 1]
+            .into_iter()
+            .collect();
+        assert_eq!(large.intersection_len(&other), 2);
+        assert_eq!(
+            find_duplicates(&[u32::MAX, 1, u32::MAX, u32::MAX]),
+            vec![u32::MAX]
+        );
+
+        let card = Scratchcard::parse("Card 7: 41 48 83 48 | 83 86 83 17 41");
+        assert_eq!(card.matches(), 2);
+        assert_eq!(
+            card.validate(),
+            Err(String::from(
+                "Card 7: duplicated winning numbers [48], duplicated numbers [83]"
+            ))
+        );
+
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let matches: Vec<u32> = process_lines(input.lines())
+            .map(|card| {
+                assert_eq!(card.validate(), Ok(()));
+                card.matches()
+            })
+            .collect();
+        assert_eq!(matches, vec![4, 2, 2, 1, 0, 0]);
+    }
+    #[test]
+    fn check_large_points() {
+        // 2^31 points per card, a u32 sum would overflow on the second one
+        let numbers: Vec<String> = (1..=32).map(|number| number.to_string()).collect();
+        let numbers: String = numbers.join(" ");
+        let card = format!("Card 1: {} | {}", numbers, numbers);
+        let input: String = vec![card; 3].join("\n");
+        assert_eq!(advent_of_code(&input), (3u64 << 31).to_string());
+
+        let numbers: Vec<String> = (1..=65).map(|number| number.to_string()).collect();
+        let numbers: String = numbers.join(" ");
+        let card = format!("Card 2: {} | {}", numbers, numbers);
+        assert_eq!(
+            sum_points(process_lines([card.as_str()])),
+            Err(String::from("Sum of points too large at card 2"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid number 4x in card: Card 1: 41 4x | 83 41")]
+    fn check_invalid_winning_number() {
+        Scratchcard::parse("Card 1: 41 4x | 83 41");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid number -8 in card: Card 1: 41 48 | -8 41")]
+    fn check_invalid_number() {
+        Scratchcard::parse("Card 1: 41 48 | -8 41");
+    }
+
+    #[test]
+    #[should_panic(expected = "Missing '|' in card: Card 1: 41 48 83 41")]
+    fn check_missing_pipe() {
+        Scratchcard::parse("Card 1: 41 48 83 41");
+    }
 }
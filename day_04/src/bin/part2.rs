@@ -1,65 +1,138 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use std::collections::VecDeque;
+use std::fmt;
+use test_utils::{Flag, SolverIo};
 mod part1;
+use part1::Scratchcard;
 
-fn advent_of_code(input_text: &str) -> String {
-    let mut cards_gains: Vec<Vec<u32>> = vec![vec![]; input_text.lines().count()];
-
-    for i in 0..input_text.lines().count() {
-        let line: &str = input_text.lines().nth(i).unwrap();
-        let (winning_numbers, scratch_result) = part1::get_scratchcards_numbers(line);
-        let matches: u32 = part1::get_scratchcards_matches(&winning_numbers, &scratch_result);
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CardBreakdown {
+    id: u32,
+    matches: u32,
+    // Original card plus every copy won from the cards above
+    copies: u64,
+    // Ids of the cards won by each copy of this card
+    spawned: Vec<u32>,
+    // Cards won that would be past the end of the table
+    overflow: u32,
+}
 
-        for j in 0..matches {
-            cards_gains[i].push(i as u32 + j + 1);
+impl fmt::Display for CardBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let spawned: Vec<String> = self.spawned.iter().map(|id| id.to_string()).collect();
+        write!(
+            f,
+            "Card {}: {} matches, {} copies, spawns [{}]",
+            self.id,
+            self.matches,
+            self.copies,
+            spawned.join(", ")
+        )?;
+        if self.overflow > 0 {
+            write!(f, ", {} past the end", self.overflow)?;
         }
+        Ok(())
     }
+}
 
-    // Cards gains, for each index, the cards indexes that are gained
-    // 1: [2, 3, 4, 5]
-    // 2: [3, 4]
-    // 3: [4, 5]
-    // 4: [5]
-    // 5: []
-    // 6: []
-
-    // Cards count, for each index, the cards count that are gained
-    // 6: 1
-    // 5: 1
-    // 4: 1 + index_5 = 1 + 1 = 2
-    // 3: 1 + index_4 + index_5 = 1 + 2 + 1 = 4
-    // 2: 1 + index_3 + index_4 = 1 + 4 + 2 = 7
-    // 1: ...
-
-    let mut cards_count: Vec<u32> = vec![1; input_text.lines().count()];
-    for i in 0..cards_gains.len() {
-        let idx: usize = cards_gains.len() - i - 1;
-
-        for j in 0..cards_gains[idx].len() {
-            let index = cards_gains[idx][j] as usize;
-            cards_count[idx] += cards_count[index]
+// Copies of each card, original included. Each card adds its copies to the next
+// `matches` cards, so only the copies already won by the next cards are kept
+fn cascade_copies(matches: impl IntoIterator<Item = u32>) -> impl Iterator<Item = u64> {
+    let mut pending_copies: VecDeque<u64> = VecDeque::new();
+    matches.into_iter().map(move |matches| {
+        let copies: u64 = 1 + pending_copies.pop_front().unwrap_or(0);
+        let matches: usize = matches as usize;
+        if pending_copies.len() < matches {
+            pending_copies.resize(matches, 0);
         }
-    }
+        for pending in pending_copies.iter_mut().take(matches) {
+            *pending += copies;
+        }
+        copies
+    })
+}
 
-    let cards_sum: u32 = cards_count.iter().sum();
+fn simulate_cascade(cards: &[Scratchcard]) -> Vec<CardBreakdown> {
+    let matches: Vec<u32> = cards.iter().map(|card| card.matches()).collect();
+    cascade_copies(matches.iter().copied())
+        .enumerate()
+        .map(|(i, copies)| {
+            let last: usize = i + matches[i] as usize;
+            CardBreakdown {
+                id: cards[i].id,
+                matches: matches[i],
+                copies,
+                spawned: cards[(i + 1)..=last.min(cards.len() - 1)]
+                    .iter()
+                    .map(|card| card.id)
+                    .collect(),
+                overflow: last.saturating_sub(cards.len() - 1) as u32,
+            }
+        })
+        .collect()
+}
+
+// Total number of cards and the ids of the cards that win past the end of the table,
+// without keeping the table
+fn count_cards(cards: impl IntoIterator<Item = Scratchcard>) -> (u64, Vec<u32>) {
+    // Cards still winning copies of the next ones (id, index of the last card won)
+    let mut winning: Vec<(u32, usize)> = Vec::new();
+    let mut num_cards: usize = 0;
+
+    let matches = cards.into_iter().enumerate().map(|(index, card)| {
+        let matches: u32 = card.matches();
+        winning.retain(|(_, last)| *last > index);
+        if matches > 0 {
+            winning.push((card.id, index + matches as usize));
+        }
+        num_cards = index + 1;
+        matches
+    });
+    let cards_sum: u64 = cascade_copies(matches).sum();
+
+    let overflowing: Vec<u32> = winning
+        .iter()
+        .filter(|(_, last)| *last >= num_cards)
+        .map(|(id, _)| *id)
+        .collect();
+    (cards_sum, overflowing)
+}
+
+#[cfg_attr(not(test), allow(unused))]
+fn advent_of_code(input_text: &str) -> String {
+    let (cards_sum, _) = count_cards(part1::process_lines(input_text.lines()));
     return cards_sum.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Stream input lines from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(
+        env!("CARGO_MANIFEST_DIR"),
+        "part2",
+        &[Flag::switch("--breakdown")],
+    );
+    let lines = io.lines();
 
-    // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    // Process data, the whole table is only kept to print it with "--breakdown"
+    let cards = part1::process_lines(lines);
+    let cards_sum: u64 = if io.has_flag("--breakdown") {
+        let cards: Vec<Scratchcard> = cards.collect();
+        let breakdown: Vec<CardBreakdown> = simulate_cascade(&cards);
+        for card in breakdown.iter() {
+            println!("{}", card);
+        }
+        breakdown.iter().map(|card| card.copies).sum()
+    } else {
+        let (cards_sum, overflowing) = count_cards(cards);
+        for id in overflowing {
+            eprintln!("Card {} wins cards past the end of the table", id);
+        }
+        cards_sum
+    };
+    let output: String = cards_sum.to_string();
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -76,4 +149,38 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_cascade() {
+        let input_test_path: &str = &get_full_path("src/bin/part2/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let cards: Vec<Scratchcard> = part1::process_lines(input.lines()).collect();
+        let breakdown = simulate_cascade(&cards);
+
+        let copies: Vec<u64> = breakdown.iter().map(|card| card.copies).collect();
+        assert_eq!(copies, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(breakdown[0].spawned, vec![2, 3, 4, 5]);
+        assert_eq!(
+            breakdown[1].to_string(),
+            "Card 2: 2 matches, 2 copies, spawns [3, 4]"
+        );
+        assert!(breakdown.iter().all(|card| card.overflow == 0));
+
+        // The last but one card wins 3 cards, only one of them exists
+        let input = "\
+Card 1: 1 2 | 1 9
+Card 2: 1 2 3 | 1 2 3
+Card 3: 5 | 6";
+        let cards: Vec<Scratchcard> = part1::process_lines(input.lines()).collect();
+        let breakdown = simulate_cascade(&cards);
+        assert_eq!(breakdown[1].spawned, vec![3]);
+        assert_eq!(breakdown[1].overflow, 2);
+        assert_eq!(
+            breakdown[1].to_string(),
+            "Card 2: 3 matches, 2 copies, spawns [3], 2 past the end"
+        );
+        assert_eq!(breakdown[2].copies, 3);
+
+        assert_eq!(count_cards(cards), (1 + 2 + 3, vec![2]));
+    }
 }
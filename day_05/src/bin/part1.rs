@@ -1,4 +1,4 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 
 #[derive(Debug)]
 pub struct MapRange {
@@ -140,20 +140,15 @@ fn advent_of_code(input_text: &str) -> String {
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part1", &[]);
+    let input: String = io.content();
 
     // Process data
     let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
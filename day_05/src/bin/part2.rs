@@ -1,4 +1,4 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 mod part1;
 
 fn process_seeds(seeds: &Vec<u64>) -> Vec<(u64, u64)> {
@@ -44,20 +44,15 @@ fn advent_of_code(input_text: &str) -> String {
     return minumun_location.to_string();
 }
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part2", &[]);
+    let input: String = io.content();
 
     // Process data
     let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -1,52 +1,172 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 
+// How the boat speed grows while the button is pressed, parameters are non negative
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charge {
+    // speed = rate · press_time
+    Linear { rate: i64 },
+    // speed = min(rate · press_time, max_speed)
+    Capped { rate: i64, max_speed: i64 },
+    // speed = acceleration · press_time²
+    Quadratic { acceleration: i64 },
+}
+
+impl Default for Charge {
+    fn default() -> Self {
+        Charge::Linear { rate: 1 }
+    }
+}
+
+impl Charge {
+    // Saturates instead of overflowing, far above any u64 record distance
+    pub fn speed(&self, press_time: i128) -> i128 {
+        match *self {
+            Charge::Linear { rate } => (rate as i128).saturating_mul(press_time),
+            Charge::Capped { rate, max_speed } => (rate as i128)
+                .saturating_mul(press_time)
+                .min(max_speed as i128),
+            Charge::Quadratic { acceleration } => (acceleration as i128)
+                .saturating_mul(press_time)
+                .saturating_mul(press_time),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match *self {
+            Charge::Linear { rate } => rate >= 0,
+            Charge::Capped { rate, max_speed } => rate >= 0 && max_speed >= 0,
+            Charge::Quadratic { acceleration } => acceleration >= 0,
+        }
+    }
+}
+
+// Times and distances are read as u64 and widened so the products below fit in i128
 pub struct Race {
-    pub time: i64,
-    pub distance: i64,
+    pub time: i128,
+    pub distance: i128,
+    pub charge: Charge,
 }
 
 impl Race {
-    fn wrap_time(&self, press_time_f: f64) -> i64 {
-        let press_time: i64 = press_time_f as i64;
+    pub fn new(time: u64, distance: u64) -> Race {
+        Race {
+            time: time as i128,
+            distance: distance as i128,
+            charge: Charge::default(),
+        }
+    }
+
+    pub fn with_charge(mut self, charge: Charge) -> Race {
+        if !charge.is_valid() {
+            panic!("Invalid charge: {:?}", charge);
+        }
+        self.charge = charge;
+        self
+    }
 
-        if press_time < 0 {
-            return 0;
-        } else if press_time > self.time {
-            return self.time;
+    // Race distance = (race_time - press_time) * speed
+    pub fn get_distance(&self, press_time: i128) -> i128 {
+        let time = self.time;
+        self.charge
+            .speed(press_time)
+            .saturating_mul(time - press_time)
+    }
+
+    fn wins(&self, press_time: i128) -> bool {
+        self.get_distance(press_time) > self.distance
+    }
+
+    // Linear charge with speed = rate · press_time:
+    // rate · press_time² - rate · race_time · press_time + race_distance = 0
+    // press_time = (rate · race_time ± √delta) / (2·rate)
+    // delta = (rate · race_time)² - 4·rate·race_distance, None if it does not fit in i128
+    fn linear_delta(&self, rate: i128) -> Option<i128> {
+        let rate_time = rate * self.time;
+        rate_time
+            .checked_mul(rate_time)?
+            .checked_sub(rate.checked_mul(4 * self.distance)?)
+    }
+
+    // Solved with integers so the boundaries are exact, returns the shortest winning press
+    fn solve_press_time(&self, rate: i128, delta: i128) -> Option<i128> {
+        let time = self.time;
+        if delta < 0 {
+            return None;
+        }
+
+        // The floor of the square root is at most one step away from the real boundary
+        let mut press_time: i128 = ((rate * time - delta.isqrt()) / (2 * rate)).max(0);
+        while press_time > 0 && self.wins(press_time - 1) {
+            press_time -= 1;
+        }
+        while press_time <= time / 2 && !self.wins(press_time) {
+            press_time += 1;
         }
-        return press_time;
+        if press_time > time / 2 {
+            return None;
+        }
+        Some(press_time)
     }
 
-    // Race distance = (race_time - press_time) * press_time
-    // press_time² - race_time * press_time + race_distance = 0
-    // press_time = -b ± √(b² - 4ac) / 2a
-    // press_time = -race_time ± √(race_time² - 4·1·race_distance) / 2
-    fn solve_press_time(&self) -> (i64, i64) {
-        let a: f64 = 1.0;
-        let b: f64 = -self.time as f64;
-        let c: f64 = self.distance as f64;
-        let delta: f64 = b * b - 4.0 * a * c;
-        if delta < 0.0 {
-            panic!("Delta is negative!");
+    // Every charge gives a distance that grows and then shrinks with the press time,
+    // so the maximum is the first press time where the next one is not farther
+    fn peak_press_time(&self) -> i128 {
+        let (mut low, mut high) = (0, self.time);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.get_distance(middle + 1) > self.get_distance(middle) {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
         }
+        low
+    }
 
-        let mut press_time_pos: f64 = (-b + delta.sqrt()) / 2.0;
-        let press_time_neg: f64 = (-b - delta.sqrt()) / 2.0 + 1.0;
+    // First press time in [low, high] where wins(press_time) == expected
+    fn bisect(&self, mut low: i128, mut high: i128, expected: bool) -> i128 {
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.wins(middle) == expected {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+        low
+    }
 
-        // If the root is exact, it doesn't win the race
-        if press_time_pos.fract() == 0.0 {
-            press_time_pos -= 1.0;
+    // Shortest and longest winning press times
+    pub fn solve_winning_range(&self) -> Option<(i128, i128)> {
+        let time = self.time;
+        if time < 0 {
+            return None;
+        }
+        match self.charge {
+            // Distance is symmetric around time / 2, so the longest winning press is time - shortest
+            Charge::Linear { rate } if rate > 0 => {
+                if let Some(delta) = self.linear_delta(rate as i128) {
+                    let press_time = self.solve_press_time(rate as i128, delta)?;
+                    return Some((press_time, time - press_time));
+                }
+            }
+            _ => (),
         }
-        let press_time_pos: i64 = self.wrap_time(press_time_pos);
-        let press_time_neg: i64 = self.wrap_time(press_time_neg);
 
-        return (press_time_pos, press_time_neg);
+        let peak = self.peak_press_time();
+        if !self.wins(peak) {
+            return None;
+        }
+        let shortest = self.bisect(0, peak, true);
+        let longest = self.bisect(peak, time + 1, false) - 1;
+        Some((shortest, longest))
     }
 
-    pub fn get_num_press_time_options(&self) -> i64 {
-        let (press_time_pos, press_time_neg) = self.solve_press_time();
-        let num_press_time_options: i64 = press_time_pos - press_time_neg + 1;
-        return num_press_time_options.abs() as i64;
+    pub fn get_num_press_time_options(&self) -> u64 {
+        match self.solve_winning_range() {
+            Some((shortest, longest)) => (longest - shortest + 1) as u64,
+            None => 0,
+        }
     }
 }
 
@@ -70,10 +190,10 @@ pub fn process_input(input: &str) -> Vec<Race> {
     }
 
     for (time, distance) in time_values.iter().zip(distance_values.iter()) {
-        let race: Race = Race {
-            time: time.parse::<i64>().unwrap(),
-            distance: distance.parse::<i64>().unwrap(),
-        };
+        let race: Race = Race::new(
+            time.parse::<u64>().unwrap(),
+            distance.parse::<u64>().unwrap(),
+        );
         races.push(race);
     }
 
@@ -84,7 +204,7 @@ fn advent_of_code(input_text: &str) -> String {
     let input: String = String::from(input_text);
 
     let races: Vec<Race> = process_input(&input);
-    let mut total_options: i64 = 1;
+    let mut total_options: u64 = 1;
     for race in races {
         let num_press_time_options = race.get_num_press_time_options();
         total_options *= num_press_time_options;
@@ -95,20 +215,15 @@ fn advent_of_code(input_text: &str) -> String {
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part1", &[]);
+    let input: String = io.content();
 
     // Process data
     let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -125,4 +240,95 @@ mod tests {
             true
         );
     }
+
+    fn brute_force(race: &Race) -> u64 {
+        (0..=race.time)
+            .filter(|&press_time| race.get_distance(press_time) > race.distance)
+            .count() as u64
+    }
+
+    // Also covers records below zero, which the input can't hold
+    fn signed_race(time: i128, distance: i128) -> Race {
+        Race {
+            time,
+            distance,
+            charge: Charge::default(),
+        }
+    }
+
+    #[test]
+    fn check_against_brute_force() {
+        for time in 0..60 {
+            for distance in -5..(time * time / 4 + 3) {
+                let race = signed_race(time, distance);
+                assert_eq!(
+                    race.get_num_press_time_options(),
+                    brute_force(&race),
+                    "time {} distance {}",
+                    time,
+                    distance
+                );
+            }
+        }
+
+        // Exact roots with a distance around 2^53
+        let time: u64 = (1 << 27) + 1;
+        let press_time: u64 = 1 << 26;
+        let distance: u64 = (time - press_time) * press_time;
+        let race = Race::new(time, distance);
+        assert_eq!(race.get_num_press_time_options(), 0);
+        let race = Race::new(time, distance - 1);
+        assert_eq!(race.get_num_press_time_options(), 2);
+
+        let race = Race::new(i64::MAX as u64, i64::MAX as u64);
+        let (shortest, _) = race.solve_winning_range().unwrap();
+        assert!(race.wins(shortest) && !race.wins(shortest - 1));
+
+        // Past i64, pressing 1 ms only reaches u64::MAX - 1
+        let race = Race::new(u64::MAX, u64::MAX);
+        let (shortest, longest) = race.solve_winning_range().unwrap();
+        assert_eq!((shortest, longest), (2, u64::MAX as i128 - 2));
+        assert_eq!(race.get_num_press_time_options(), u64::MAX - 3);
+        let input = format!("Time: {}\nDistance: {}", u64::MAX, u64::MAX);
+        assert_eq!(advent_of_code(&input), (u64::MAX - 3).to_string());
+    }
+
+    #[test]
+    fn check_charges() {
+        let charges = [
+            Charge::Linear { rate: 0 },
+            Charge::Linear { rate: 3 },
+            Charge::Capped {
+                rate: 2,
+                max_speed: 7,
+            },
+            Charge::Capped {
+                rate: 5,
+                max_speed: 0,
+            },
+            Charge::Quadratic { acceleration: 1 },
+            Charge::Quadratic { acceleration: 4 },
+        ];
+        for charge in charges {
+            for time in 0..40 {
+                for distance in [-1, 0, 1, 10, 55, 200, 1000, 5000] {
+                    let race = signed_race(time, distance).with_charge(charge);
+                    assert_eq!(
+                        race.get_num_press_time_options(),
+                        brute_force(&race),
+                        "{:?} time {} distance {}",
+                        charge,
+                        time,
+                        distance
+                    );
+                }
+            }
+        }
+
+        // Too big for the closed form, the search still finds the exact boundaries
+        let race = Race::new(i64::MAX as u64, i64::MAX as u64)
+            .with_charge(Charge::Linear { rate: i64::MAX });
+        let (shortest, longest) = race.solve_winning_range().unwrap();
+        assert_eq!((shortest, longest), (1, i64::MAX as i128 - 1));
+    }
 }
@@ -1,4 +1,4 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 mod part1;
 
 pub fn process_input(input: &str) -> part1::Race {
@@ -11,13 +11,10 @@ pub fn process_input(input: &str) -> part1::Race {
     let time_line: String = time_line.replace(" ", "");
     let distance_line: String = distance_line.replace(" ", "");
 
-    let race_time: i64 = time_line.parse::<i64>().unwrap();
-    let race_distance: i64 = distance_line.parse::<i64>().unwrap();
+    let race_time: u64 = time_line.parse::<u64>().unwrap();
+    let race_distance: u64 = distance_line.parse::<u64>().unwrap();
 
-    let race: part1::Race = part1::Race {
-        time: race_time,
-        distance: race_distance,
-    };
+    let race: part1::Race = part1::Race::new(race_time, race_distance);
 
     return race;
 }
@@ -32,20 +29,15 @@ fn advent_of_code(input_text: &str) -> String {
 }
 
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part2", &[]);
+    let input: String = io.content();
 
     // Process data
     let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Cards {
@@ -137,27 +137,24 @@ pub fn card_str_to_vec(cards_str: &str, use_jokers: bool) -> Vec<Cards> {
     return cards;
 }
 
-pub fn process_input(input: &str) -> Vec<(&str, i64)> {
-    let mut lines: Vec<(&str, i64)> = Vec::new();
+// Ej: 32T3K 765
+pub fn process_line(line: &str) -> (&str, i64) {
+    let line_values: Vec<&str> = line.split_whitespace().collect();
+    let cards_str: &str = line_values[0];
+    let bid: i64 = line_values[1].parse::<i64>().unwrap();
 
-    for line in input.lines() {
-        let line_values: Vec<&str> = line.split_whitespace().collect();
-        let cards_str: &str = line_values[0];
-        let bid: i64 = line_values[1].parse::<i64>().unwrap();
-
-        let data: (&str, i64) = (cards_str, bid);
-        lines.push(data);
-    }
-
-    return lines;
+    return (cards_str, bid);
 }
 
-fn advent_of_code(input_text: &str) -> String {
-    let input: String = String::from(input_text);
-    let data: Vec<(&str, i64)> = process_input(&input);
+// Hands must be sorted, so the parsed hands are kept but not the input text
+fn total_winnings<I>(lines: I) -> String
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
     let mut hands: Vec<Hand> = Vec::new();
-    for i in 0..data.len() {
-        let (cards_str, bid): (&str, i64) = data[i];
+    for line in lines {
+        let (cards_str, bid): (&str, i64) = process_line(line.as_ref());
         let cards: Vec<Cards> = card_str_to_vec(cards_str, false);
         let hand: Hand = Hand::new(&cards, bid);
         hands.push(hand);
@@ -166,22 +163,22 @@ fn advent_of_code(input_text: &str) -> String {
     return bid.to_string();
 }
 
+#[cfg_attr(not(test), allow(unused))]
+fn advent_of_code(input_text: &str) -> String {
+    total_winnings(input_text.lines())
+}
+
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Stream input lines from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part1", &[]);
+    let lines = io.lines();
 
     // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    let output: String = total_winnings(lines);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 mod part1;
 
 fn replace_card(cards: &mut Vec<part1::Cards>, card: part1::Cards, new_card: part1::Cards) {
@@ -58,12 +58,14 @@ fn replace_jokers(cards: &Vec<part1::Cards>) -> Vec<part1::Cards> {
     return cards;
 }
 
-fn advent_of_code(input_text: &str) -> String {
-    let input: String = String::from(input_text);
-    let data: Vec<(&str, i64)> = part1::process_input(&input);
+fn total_winnings<I>(lines: I) -> String
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
     let mut hands: Vec<part1::Hand> = Vec::new();
-    for i in 0..data.len() {
-        let (cards_str, bid): (&str, i64) = data[i];
+    for line in lines {
+        let (cards_str, bid): (&str, i64) = part1::process_line(line.as_ref());
         let cards: Vec<part1::Cards> = part1::card_str_to_vec(cards_str, true);
         let cards_without_jokers: Vec<part1::Cards> = replace_jokers(&cards);
         let strength: part1::HandStrength = part1::Hand::compute_strength(&cards_without_jokers);
@@ -78,21 +80,21 @@ fn advent_of_code(input_text: &str) -> String {
     return bid.to_string();
 }
 
+#[cfg_attr(not(test), allow(unused))]
+fn advent_of_code(input_text: &str) -> String {
+    total_winnings(input_text.lines())
+}
+
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Stream input lines from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part2", &[]);
+    let lines = io.lines();
 
     // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    let output: String = total_winnings(lines);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -1,88 +1,72 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use lib_utils::cycle::find_cycle;
+use std::collections::HashMap;
+use test_utils::SolverIo;
 mod part1;
 use num_integer;
 
+// Ghost state: current node and index of the next instruction
+type Ghost<'a> = (&'a str, usize);
+
+fn ghost_period(
+    start: &str,
+    instructions: &[char],
+    nodes: &HashMap<String, (String, String)>,
+) -> u64 {
+    let step = |&(node, instructions_index): &Ghost| -> Ghost {
+        let next_node = match instructions[instructions_index] {
+            'R' => nodes.get(node).map(|x| x.1.as_str()),
+            'L' => nodes.get(node).map(|x| x.0.as_str()),
+            _ => panic!("Invalid instruction!"),
+        };
+        let next_node = match next_node {
+            Some(next_node) => next_node,
+            None => panic!("Unknown node: {}", node),
+        };
+        (next_node, (instructions_index + 1) % instructions.len())
+    };
+
+    let start_state: Ghost = (start, 0);
+    let cycle = find_cycle(&start_state, step);
+
+    // Every multiple of the period lands on the same node, that must be an end
+    let (node, _) = cycle.state_after(&start_state, step, cycle.period);
+    if !node.ends_with('Z') {
+        panic!(
+            "Ghost from {} is not at an end node every {} steps",
+            start, cycle.period
+        );
+    }
+    cycle.period
+}
+
 fn advent_of_code(input_text: &str) -> String {
     let input: String = String::from(input_text);
     let (instructions, nodes) = part1::process_input(&input);
+    let instructions_chars = instructions.chars().collect::<Vec<char>>();
 
-    let starts_node: Vec<String> = nodes
+    let cycles_steps: Vec<u64> = nodes
         .keys()
         .filter(|x| x.ends_with('A'))
-        .map(|x| x.to_string())
+        .map(|start| ghost_period(start, &instructions_chars, &nodes))
         .collect();
 
-    let ends_node: Vec<String> = nodes
-        .keys()
-        .filter(|x| x.ends_with('Z'))
-        .map(|x| x.to_string())
-        .collect();
-
-    let mut steps: i128 = 0;
-    let mut instructions_index: usize = 0;
-    let instructions_chars = instructions.chars().collect::<Vec<char>>();
-    let mut current_nodes: Vec<String> = starts_node.clone();
-    let mut last_step: Vec<i128> = vec![0; current_nodes.len()];
-    let mut cycles_steps: Vec<i128> = vec![0; current_nodes.len()];
-
-    'outer: loop {
-        if instructions_index == instructions_chars.len() {
-            instructions_index = 0;
-        }
-
-        steps += 1;
-
-        for i in 0..current_nodes.len() {
-            let current_node = &current_nodes[i];
-
-            let next_node = match instructions_chars[instructions_index] {
-                'R' => nodes.get(current_node).map(|x| &x.1),
-                'L' => nodes.get(current_node).map(|x| &x.0),
-                _ => panic!("Invalid instruction!"),
-            };
-
-            // Check if next node is in ends_node
-            if next_node.is_none() {
-                !panic!("Invalid instruction!");
-            }
-            let next_node = next_node.unwrap().to_string();
-
-            if cycles_steps[i] == 0 && ends_node.contains(&next_node) {
-                cycles_steps[i] = steps - last_step[i];
-                last_step[i] = steps;
-
-                // Check if all cycles_steps are different from 0
-                if cycles_steps.iter().all(|&x| x != 0) {
-                    break 'outer;
-                }
-            }
-            current_nodes[i] = next_node;
-        }
-        instructions_index += 1;
-    }
-
-    let mut lcm_steps: usize = cycles_steps[0] as usize;
+    let mut lcm_steps: u64 = cycles_steps[0];
     for i in 1..cycles_steps.len() {
-        lcm_steps = num_integer::lcm(lcm_steps, cycles_steps[i] as usize);
+        lcm_steps = num_integer::lcm(lcm_steps, cycles_steps[i]);
     }
     return lcm_steps.to_string();
 }
 
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part2", &[]);
+    let input: String = io.content();
 
     // Process data
     let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
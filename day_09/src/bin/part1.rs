@@ -1,4 +1,4 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 
 pub fn find_sequence(data: &Vec<i64>) -> i64 {
     let mut differences: Vec<i64> = Vec::new();
@@ -22,7 +22,7 @@ pub fn find_sequence(data: &Vec<i64>) -> i64 {
     return data_last + find_sequence(&differences);
 }
 
-pub fn sum_sequence(data: Vec<Vec<i64>>) -> i64 {
+pub fn sum_sequence(data: impl IntoIterator<Item = Vec<i64>>) -> i64 {
     let mut sequence: i64 = 0;
     for line in data {
         let sequence_i = find_sequence(&line);
@@ -31,40 +31,40 @@ pub fn sum_sequence(data: Vec<Vec<i64>>) -> i64 {
     return sequence;
 }
 
-pub fn process_input(input: &str) -> Vec<Vec<i64>> {
-    let mut output: Vec<Vec<i64>> = Vec::new();
-    for line in input.lines() {
-        let mut line_vec: Vec<i64> = Vec::new();
-        for num in line.split(" ") {
-            line_vec.push(num.parse::<i64>().unwrap());
-        }
-        output.push(line_vec);
+pub fn process_line(line: &str) -> Vec<i64> {
+    let mut line_vec: Vec<i64> = Vec::new();
+    for num in line.split(" ") {
+        line_vec.push(num.parse::<i64>().unwrap());
     }
-    return output;
+    return line_vec;
 }
 
+// Each line is an independent sequence, parsed as it is read
+pub fn process_lines<I>(lines: I) -> impl Iterator<Item = Vec<i64>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    lines.into_iter().map(|line| process_line(line.as_ref()))
+}
+
+#[cfg_attr(not(test), allow(unused))]
 fn advent_of_code(input_text: &str) -> String {
-    let input: String = String::from(input_text);
-    let data = process_input(&input);
+    let data = process_lines(input_text.lines());
     return sum_sequence(data).to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Stream input lines from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part1", &[]);
+    let lines = io.lines();
 
     // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    let output: String = sum_sequence(process_lines(lines)).to_string();
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
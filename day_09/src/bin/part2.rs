@@ -1,29 +1,35 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 mod part1;
-use part1::{process_input, sum_sequence};
+use part1::{process_lines, sum_sequence};
 
-fn advent_of_code(input_text: &str) -> String {
-    let input: String = String::from(input_text);
-    let mut data = process_input(&input);
-    data.iter_mut().for_each(|x| x.reverse());
+// Extrapolating backwards is extrapolating the reversed sequence
+fn sum_previous<I>(lines: I) -> String
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let data = process_lines(lines).map(|mut x| {
+        x.reverse();
+        x
+    });
     return sum_sequence(data).to_string();
 }
 
+#[cfg_attr(not(test), allow(unused))]
+fn advent_of_code(input_text: &str) -> String {
+    sum_previous(input_text.lines())
+}
+
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Stream input lines from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part2", &[]);
+    let lines = io.lines();
 
     // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    let output: String = sum_previous(lines);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -1,7 +1,8 @@
 use colored::*;
 use day_10::Matrix;
+use lib_utils::render::{write_svg, CellStyle, Color, RenderOptions, Shape};
 use std::fmt;
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::{Flag, SolverIo};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Direction {
@@ -34,6 +35,33 @@ impl fmt::Display for Direction {
     }
 }
 
+pub fn direction_style(direction: &Direction) -> Option<CellStyle> {
+    let pipe_color = Color::rgb(40, 70, 160);
+    let pipe = |north, south, west, east| {
+        Some(CellStyle::new(
+            pipe_color,
+            Shape::Pipe {
+                north,
+                south,
+                west,
+                east,
+            },
+        ))
+    };
+    match direction {
+        Direction::Start => Some(CellStyle::new(Color::rgb(230, 140, 20), Shape::Square)),
+        Direction::V => pipe(true, true, false, false),
+        Direction::H => pipe(false, false, true, true),
+        Direction::NE => pipe(true, false, false, true),
+        Direction::NW => pipe(true, false, true, false),
+        Direction::SW => pipe(false, true, true, false),
+        Direction::SE => pipe(false, true, false, true),
+        Direction::None => None,
+        Direction::Inner => Some(CellStyle::new(Color::rgb(60, 180, 75), Shape::Square)),
+        Direction::Outter => Some(CellStyle::new(Color::rgb(240, 200, 200), Shape::Square)),
+    }
+}
+
 fn char_to_direction(ch: char) -> Direction {
     match ch {
         'S' => Direction::Start,
@@ -227,31 +255,47 @@ pub fn get_path_length(matrix: &Matrix<i8>) -> i64 {
     return path_length;
 }
 
-fn advent_of_code(input_text: &str) -> String {
+// Loop of the input and the distance to its farthest point, shared by the
+// answer and the rendering
+pub fn solve_loop(input_text: &str) -> (Matrix<Direction>, i64) {
     let input: String = String::from(input_text);
     let (matrix, start_coords) = process_input(&input);
     let (path, visited_cells) = find_path(&matrix, start_coords);
     let farest_length = get_path_length(&visited_cells) / 2;
-    println!("Path:\n{}", path);
+    eprintln!("Path:\n{}", path);
+    (path, farest_length)
+}
+
+#[cfg_attr(not(test), allow(unused))]
+fn advent_of_code(input_text: &str) -> String {
+    let (_, farest_length) = solve_loop(input_text);
     return farest_length.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part1", &[Flag::value("--svg")]);
+    let input: String = io.content();
 
     // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    let (path, farest_length) = solve_loop(&input);
+    let output: String = farest_length.to_string();
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
+
+    // Render the loop to the "--svg <path>" image
+    if let Some(image_path) = io.flag_value("--svg") {
+        if let Err(error) = write_svg(
+            image_path,
+            &path,
+            &RenderOptions::default(),
+            direction_style,
+        ) {
+            eprintln!("Unable to write image {}: {}", image_path, error);
+        }
+    }
 }
 
 #[cfg(test)]
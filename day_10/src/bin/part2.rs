@@ -1,6 +1,8 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use day_10::Matrix;
+use lib_utils::render::{write_svg, RenderOptions};
+use test_utils::{Flag, SolverIo};
 mod part1;
-use part1::{find_path, process_input, Direction};
+use part1::{direction_style, find_path, process_input, Direction};
 
 fn get_countour_cross(contour: &Vec<Direction>) -> i8 {
     // Count number of Direction::V, Direction::NE, Direction::NW
@@ -28,11 +30,10 @@ fn get_countour_cross(contour: &Vec<Direction>) -> i8 {
     return countour_cross;
 }
 
-fn advent_of_code(input_text: &str) -> String {
-    let input: String = String::from(input_text);
-    let (matrix, start_coords) = process_input(&input);
-    let (mut path, visited_cells) = find_path(&matrix, start_coords);
-
+fn mark_inner_points(
+    path: &mut Matrix<Direction>,
+    visited_cells: &Matrix<i8>,
+) -> Vec<(usize, usize)> {
     let mut inner_points: Vec<(usize, usize)> = Vec::new();
     for row in 1..visited_cells.rows - 1 {
         for col in 1..visited_cells.cols - 1 {
@@ -51,27 +52,51 @@ fn advent_of_code(input_text: &str) -> String {
             }
         }
     }
+    inner_points
+}
+
+// Loop of the input with its enclosed tiles marked and their number, shared
+// by the answer and the rendering
+fn solve_enclosed(input_text: &str) -> (Matrix<Direction>, usize) {
+    let input: String = String::from(input_text);
+    let (matrix, start_coords) = process_input(&input);
+    let (mut path, visited_cells) = find_path(&matrix, start_coords);
+    let inner_points = mark_inner_points(&mut path, &visited_cells);
+
+    eprintln!("Path:\n{}", path);
+    (path, inner_points.len())
+}
 
-    println!("Path:\n{}", path);
-    return inner_points.len().to_string();
+#[cfg_attr(not(test), allow(unused))]
+fn advent_of_code(input_text: &str) -> String {
+    let (_, num_inner_points) = solve_enclosed(input_text);
+    return num_inner_points.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part2", &[Flag::value("--svg")]);
+    let input: String = io.content();
 
     // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    let (path, num_inner_points) = solve_enclosed(&input);
+    let output: String = num_inner_points.to_string();
+
+    // Print result and write it to file
+    io.report(&output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Render the loop and the enclosed area to the "--svg <path>" image
+    if let Some(image_path) = io.flag_value("--svg") {
+        if let Err(error) = write_svg(
+            image_path,
+            &path,
+            &RenderOptions::default(),
+            direction_style,
+        ) {
+            eprintln!("Unable to write image {}: {}", image_path, error);
+        }
+    }
 }
 
 #[cfg(test)]
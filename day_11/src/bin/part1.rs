@@ -1,25 +1,110 @@
-use day_10::Matrix;
-use test_utils::{get_file_content, get_full_path, write_file_content};
-
-pub fn expand_universe(space: &mut Matrix<char>) {
-    let vec_of_space = vec!['*'; space.cols];
-    let space_row_aux = space.rows;
-    for i in 0..space_row_aux {
-        let row = space_row_aux - i - 1;
-        let row_vector = space.get_row_vector(row, 0, space.cols);
-        if row_vector.iter().all(|&x| x == '.') {
-            space.insert_row(row, vec_of_space.clone());
+use lib_utils::metric::{Manhattan, Metric};
+use lib_utils::render::{write_svg, CellStyle, Color, RenderOptions, Shape};
+use lib_utils::Matrix;
+use std::collections::BTreeMap;
+use test_utils::{Flag, SolverIo};
+
+#[derive(Debug, Clone)]
+pub struct Universe {
+    pub galaxies: Vec<(usize, usize)>,
+    // Prefix sums: number of empty rows/cols before each index
+    pub empty_rows_before: Vec<u64>,
+    pub empty_cols_before: Vec<u64>,
+}
+
+impl Universe {
+    pub fn new(space: &Matrix<char>) -> Universe {
+        let mut galaxies: Vec<(usize, usize)> = Vec::new();
+        for row in 0..space.rows {
+            for col in 0..space.cols {
+                if *space.at(row, col) == '#' {
+                    galaxies.push((row, col));
+                }
+            }
+        }
+
+        let mut row_has_galaxy: Vec<bool> = vec![false; space.rows];
+        let mut col_has_galaxy: Vec<bool> = vec![false; space.cols];
+        for &(row, col) in galaxies.iter() {
+            row_has_galaxy[row] = true;
+            col_has_galaxy[col] = true;
         }
+        Universe {
+            galaxies,
+            empty_rows_before: count_empty_before(&row_has_galaxy),
+            empty_cols_before: count_empty_before(&col_has_galaxy),
+        }
+    }
+
+    #[cfg_attr(not(test), allow(unused))]
+    pub fn is_empty_row(&self, row: usize) -> bool {
+        self.empty_rows_before[row + 1] > self.empty_rows_before[row]
+    }
+
+    #[cfg_attr(not(test), allow(unused))]
+    pub fn is_empty_col(&self, col: usize) -> bool {
+        self.empty_cols_before[col + 1] > self.empty_cols_before[col]
+    }
+
+    // Each empty row/col is replaced by expansion_factor rows/cols, any factor works as long
+    // as the expanded coordinates fit in u64
+    pub fn get_galaxies_coordinates(
+        &self,
+        expansion_factor: u64,
+    ) -> Result<Vec<(u64, u64)>, String> {
+        let extra = expansion_factor.saturating_sub(1);
+        let expand = |position: usize, empty_before: u64| {
+            empty_before
+                .checked_mul(extra)
+                .and_then(|offset| offset.checked_add(position as u64))
+        };
+        self.galaxies
+            .iter()
+            .map(|&(row, col)| {
+                match (
+                    expand(row, self.empty_rows_before[row]),
+                    expand(col, self.empty_cols_before[col]),
+                ) {
+                    (Some(row), Some(col)) => Ok((row, col)),
+                    _ => Err(format!(
+                        "Expansion factor {} moves galaxy ({}, {}) past u64",
+                        expansion_factor, row, col
+                    )),
+                }
+            })
+            .collect()
     }
 
-    let vec_of_space = vec!['*'; space.rows];
-    let space_col_aux = space.cols;
-    for i in 0..space_col_aux {
-        let col = space_col_aux - i - 1;
-        let col_vector = space.get_col_vector(col, 0, space.rows);
-        if col_vector.iter().all(|&x| x == '.' || x == '*') {
-            space.insert_col(col, vec_of_space.clone());
+    // Copy of the space with the empty rows and cols filled with '*'
+    #[cfg_attr(not(test), allow(unused))]
+    pub fn mark_empty_space(&self, space: &Matrix<char>) -> Matrix<char> {
+        let mut marked_space = space.clone();
+        for row in 0..space.rows {
+            for col in 0..space.cols {
+                if self.is_empty_row(row) || self.is_empty_col(col) {
+                    marked_space.set_value('*', row, col);
+                }
+            }
         }
+        marked_space
+    }
+}
+
+fn count_empty_before(has_galaxy: &[bool]) -> Vec<u64> {
+    let mut empty_before: Vec<u64> = Vec::with_capacity(has_galaxy.len() + 1);
+    empty_before.push(0);
+    for (index, &has_galaxy) in has_galaxy.iter().enumerate() {
+        empty_before.push(empty_before[index] + !has_galaxy as u64);
+    }
+    empty_before
+}
+
+#[cfg_attr(not(test), allow(unused))]
+pub fn space_style(cell: &char) -> Option<CellStyle> {
+    match cell {
+        '#' => Some(CellStyle::new(Color::rgb(230, 180, 30), Shape::Circle)),
+        '*' => Some(CellStyle::new(Color::rgb(210, 220, 240), Shape::Square)),
+        _ => None,
     }
 }
 
@@ -39,104 +124,246 @@ pub fn process_input(input: &str) -> Matrix<char> {
     space
 }
 
-pub fn get_galaxies_coordinates(
-    space: &Matrix<char>,
-    expansion_factor: &usize,
-) -> Vec<(usize, usize)> {
-    let mut galaxies_coordinates: Vec<(usize, usize)> = Vec::new();
-    for row in 0..space.rows {
-        for col in 0..space.cols {
-            if *space.at(row, col) == '#' {
-                // Count how many '*' are between the galaxy and the border
-
-                let horizontal_count_to_border = match col {
-                    0 => 0,
-                    _ => {
-                        let left_part = space.get_row_vector(row, 0, col);
-                        left_part.iter().filter(|&x| *x == '*').count()
-                    }
-                };
-                let vertical_count_to_border = match row {
-                    0 => 0,
-                    _ => {
-                        let upper_part = space.get_col_vector(col, 0, row);
-                        upper_part.iter().filter(|&x| *x == '*').count()
-                    }
-                };
-
-                let original_row_coordinate = row - vertical_count_to_border;
-                let original_col_coordinate = col - horizontal_count_to_border;
-
-                galaxies_coordinates.push((
-                    expansion_factor * vertical_count_to_border + original_row_coordinate,
-                    expansion_factor * horizontal_count_to_border + original_col_coordinate,
-                ));
+#[derive(Debug, Clone)]
+pub struct GalaxyDistances {
+    pub coordinates: Vec<(u64, u64)>,
+}
+
+impl GalaxyDistances {
+    // Every distance fits in u64 when the farthest one does, so queries never overflow
+    pub fn new(coordinates: Vec<(u64, u64)>) -> Result<GalaxyDistances, String> {
+        let distances = GalaxyDistances { coordinates };
+        match distances.farthest() {
+            Some((galaxy1, galaxy2, distance)) if distance > u64::MAX as u128 => Err(format!(
+                "Distance between galaxies {} and {} does not fit in u64",
+                galaxy1 + 1,
+                galaxy2 + 1
+            )),
+            _ => Ok(distances),
+        }
+    }
+
+    pub fn distance(&self, galaxy1: usize, galaxy2: usize) -> u64 {
+        Manhattan
+            .distance(self.coordinates[galaxy1], self.coordinates[galaxy2])
+            .unwrap()
+    }
+
+    // None when the sum does not fit in u64
+    pub fn sum(&self) -> Option<u64> {
+        Manhattan.pairwise_sum(&self.coordinates)
+    }
+
+    // Returns (galaxy1, galaxy2, distance)
+    pub fn closest_pair(&self) -> Option<(usize, usize, u64)> {
+        self.nearest_neighbours()
+            .iter()
+            .enumerate()
+            .filter_map(|(galaxy, nearest)| nearest.map(|(other, d)| (galaxy, other, d)))
+            .min_by_key(|&(_, _, distance)| distance)
+    }
+
+    // Returns (galaxy1, galaxy2, distance)
+    pub fn farthest_pair(&self) -> Option<(usize, usize, u64)> {
+        self.farthest()
+            .map(|(galaxy1, galaxy2, distance)| (galaxy1, galaxy2, distance as u64))
+    }
+
+    fn farthest(&self) -> Option<(usize, usize, u128)> {
+        if self.coordinates.len() < 2 {
+            return None;
+        }
+        // Rotating 45 degrees turns Manhattan distance into the largest
+        // spread along the two diagonals
+        let sums: Vec<i128> = self
+            .coordinates
+            .iter()
+            .map(|&(row, col)| row as i128 + col as i128)
+            .collect();
+        let differences: Vec<i128> = self
+            .coordinates
+            .iter()
+            .map(|&(row, col)| row as i128 - col as i128)
+            .collect();
+        let spread = |values: &Vec<i128>| {
+            let min_index = (0..values.len()).min_by_key(|&i| values[i]).unwrap();
+            let max_index = (0..values.len()).max_by_key(|&i| values[i]).unwrap();
+            let distance = (values[max_index] - values[min_index]) as u128;
+            (min_index, max_index, distance)
+        };
+        [spread(&sums), spread(&differences)]
+            .into_iter()
+            .max_by_key(|&(_, _, distance)| distance)
+    }
+
+    // Number of pairs at each distance
+    pub fn histogram(&self) -> BTreeMap<u64, usize> {
+        let mut histogram: BTreeMap<u64, usize> = BTreeMap::new();
+        for galaxy1 in 0..self.coordinates.len() {
+            for galaxy2 in (galaxy1 + 1)..self.coordinates.len() {
+                *histogram
+                    .entry(self.distance(galaxy1, galaxy2))
+                    .or_insert(0) += 1;
             }
         }
+        histogram
     }
-    galaxies_coordinates
-}
 
-pub fn compute_manhattan_distance_between(row1: i64, col1: i64, row2: i64, col2: i64) -> i64 {
-    let row_distance = (row1 - row2).abs();
-    let col_distance = (col1 - col2).abs();
-    let distance = row_distance + col_distance;
-    distance
-}
+    // For each galaxy, its nearest galaxy and the distance to it
+    pub fn nearest_neighbours(&self) -> Vec<Option<(usize, u64)>> {
+        let mut order: Vec<usize> = (0..self.coordinates.len()).collect();
+        order.sort_by_key(|&galaxy| self.coordinates[galaxy].0);
+
+        let mut nearest: Vec<Option<(usize, u64)>> = vec![None; self.coordinates.len()];
+        for (position, &galaxy) in order.iter().enumerate() {
+            let mut best: Option<(usize, u64)> = None;
+            self.update_nearest(galaxy, order[position + 1..].iter(), &mut best);
+            self.update_nearest(galaxy, order[..position].iter().rev(), &mut best);
+            nearest[galaxy] = best;
+        }
+        nearest
+    }
 
-pub fn compute_distance_between_galaxies(space: &Matrix<char>, expansion_factor: &usize) -> i64 {
-    let galaxies_coordinates: Vec<(usize, usize)> =
-        get_galaxies_coordinates(&space, &expansion_factor);
-    let mut distance_sum: i64 = 0;
-
-    for i in 0..galaxies_coordinates.len() {
-        for j in (i + 1)..galaxies_coordinates.len() {
-            let (row1, col1) = galaxies_coordinates[i];
-            let (row2, col2) = galaxies_coordinates[j];
-            if row1 == row2 && col1 == col2 {
-                continue;
+    // Walk away from the galaxy in row order, stopping once the row gap alone
+    // is already larger than the best distance found
+    fn update_nearest<'a>(
+        &self,
+        galaxy: usize,
+        candidates: impl Iterator<Item = &'a usize>,
+        best: &mut Option<(usize, u64)>,
+    ) {
+        let row = self.coordinates[galaxy].0;
+        for &other in candidates {
+            let row_gap = row.abs_diff(self.coordinates[other].0);
+            if best.is_some_and(|(_, distance)| row_gap >= distance) {
+                break;
+            }
+            let distance = self.distance(galaxy, other);
+            if best.is_none_or(|(_, best_distance)| distance < best_distance) {
+                *best = Some((other, distance));
             }
-            let distance = compute_manhattan_distance_between(
-                row1 as i64,
-                col1 as i64,
-                row2 as i64,
-                col2 as i64,
-            );
-            distance_sum += distance;
         }
     }
-    distance_sum
+}
+
+// Report printed with "--stats", galaxies numbered from 1 as in the puzzle text
+pub fn galaxy_statistics(universe: &Universe, expansion_factor: u64) -> Result<String, String> {
+    let distances = GalaxyDistances::new(universe.get_galaxies_coordinates(expansion_factor)?)?;
+    let mut report = format!("Galaxies: {}\n", distances.coordinates.len());
+    if let Some(sum) = distances.sum() {
+        report.push_str(&format!("Sum of distances: {}\n", sum));
+    }
+    if let Some((galaxy1, galaxy2, distance)) = distances.closest_pair() {
+        report.push_str(&format!(
+            "Closest pair: {} and {}, distance {}\n",
+            galaxy1 + 1,
+            galaxy2 + 1,
+            distance
+        ));
+    }
+    if let Some((galaxy1, galaxy2, distance)) = distances.farthest_pair() {
+        report.push_str(&format!(
+            "Farthest pair: {} and {}, distance {}\n",
+            galaxy1 + 1,
+            galaxy2 + 1,
+            distance
+        ));
+    }
+    let most_isolated = distances
+        .nearest_neighbours()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(galaxy, nearest)| nearest.map(|(other, d)| (galaxy, other, d)))
+        .max_by_key(|&(_, _, distance)| distance);
+    if let Some((galaxy, nearest, distance)) = most_isolated {
+        report.push_str(&format!(
+            "Most isolated: {}, nearest galaxy {} at distance {}\n",
+            galaxy + 1,
+            nearest + 1,
+            distance
+        ));
+    }
+    report.push_str("Pairs per distance:\n");
+    for (distance, count) in distances.histogram() {
+        report.push_str(&format!("{}: {}\n", distance, count));
+    }
+    Ok(report)
+}
+
+pub fn compute_distance_between_galaxies<M: Metric>(
+    universe: &Universe,
+    expansion_factor: u64,
+    metric: &M,
+) -> Result<u64, String> {
+    // A grid metric measures the galaxies where they are in the input, not where they end up
+    if expansion_factor != 1 && !metric.accepts_any_point() {
+        return Err(format!(
+            "The metric only measures the unexpanded universe, not expansion factor {}",
+            expansion_factor
+        ));
+    }
+    let galaxies_coordinates: Vec<(u64, u64)> =
+        universe.get_galaxies_coordinates(expansion_factor)?;
+    metric
+        .pairwise_sum(&galaxies_coordinates)
+        .ok_or_else(|| String::from("Sum of distances between galaxies does not fit in u64"))
 }
 
 fn advent_of_code(input_text: &str) -> String {
     let input: String = String::from(input_text);
-    let mut space = process_input(&input);
-    expand_universe(&mut space);
-    let distance = compute_distance_between_galaxies(&space, &1);
-    return distance.to_string();
+    let space = process_input(&input);
+    let universe = Universe::new(&space);
+    match compute_distance_between_galaxies(&universe, 2, &Manhattan) {
+        Ok(distance) => distance.to_string(),
+        Err(error) => panic!("{}", error),
+    }
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(
+        env!("CARGO_MANIFEST_DIR"),
+        "part1",
+        &[Flag::switch("--stats"), Flag::value("--svg")],
+    );
+    let input: String = io.content();
 
     // Process data
     let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
+
+    // Distance statistics with "--stats"
+    if io.has_flag("--stats") {
+        let universe = Universe::new(&process_input(&input));
+        match galaxy_statistics(&universe, 2) {
+            Ok(report) => eprint!("{}", report),
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+
+    // Render the universe and its empty space to the "--svg <path>" image
+    if let Some(image_path) = io.flag_value("--svg") {
+        let space = process_input(&input);
+        let marked_space = Universe::new(&space).mark_empty_space(&space);
+        if let Err(error) = write_svg(
+            image_path,
+            &marked_space,
+            &RenderOptions::default(),
+            space_style,
+        ) {
+            eprintln!("Unable to write image {}: {}", image_path, error);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use lib_utils::metric::{Chebyshev, EuclideanSquared, GridPath};
+    use lib_utils::render::render_svg;
     use test_utils::{get_full_path, test_advent_of_code};
 
     #[test]
@@ -148,4 +375,112 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_expansion_factors() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let universe = Universe::new(&process_input(&input));
+        assert_eq!(
+            compute_distance_between_galaxies(&universe, 2, &Manhattan),
+            Ok(374)
+        );
+        assert_eq!(
+            compute_distance_between_galaxies(&universe, 10, &Manhattan),
+            Ok(1030)
+        );
+        assert_eq!(
+            compute_distance_between_galaxies(&universe, 100, &Manhattan),
+            Ok(8410)
+        );
+
+        // Any factor works while the expanded coordinates fit in u64
+        let coordinates = universe.get_galaxies_coordinates(u64::MAX / 4).unwrap();
+        assert_eq!(coordinates[0], (0, u64::MAX / 4 + 2));
+        assert!(universe.get_galaxies_coordinates(u64::MAX / 2).is_err());
+        assert!(compute_distance_between_galaxies(&universe, u64::MAX / 4, &Manhattan).is_err());
+    }
+
+    #[test]
+    fn check_metrics() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let space = process_input(&input);
+        let universe = Universe::new(&space);
+
+        // Without obstacles the shortest path is the Manhattan distance
+        let grid_path = GridPath::new(&space, |_: &char| false);
+        assert_eq!(
+            compute_distance_between_galaxies(&universe, 1, &grid_path),
+            compute_distance_between_galaxies(&universe, 1, &Manhattan)
+        );
+        assert!(compute_distance_between_galaxies(&universe, 2, &grid_path).is_err());
+        assert!(
+            compute_distance_between_galaxies(&universe, 2, &Chebyshev)
+                <= compute_distance_between_galaxies(&universe, 2, &Manhattan)
+        );
+        assert!(
+            compute_distance_between_galaxies(&universe, 1_000_000, &EuclideanSquared)
+                > compute_distance_between_galaxies(&universe, 1_000_000, &Chebyshev)
+        );
+    }
+
+    #[test]
+    fn check_distance_statistics() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let universe = Universe::new(&process_input(&input));
+        let distances =
+            GalaxyDistances::new(universe.get_galaxies_coordinates(2).unwrap()).unwrap();
+
+        // Compare against the exhaustive pairwise loop
+        let n = distances.coordinates.len();
+        let pairs: Vec<(usize, usize, u64)> = (0..n)
+            .flat_map(|i| ((i + 1)..n).map(move |j| (i, j)))
+            .map(|(i, j)| (i, j, distances.distance(i, j)))
+            .collect();
+        let min_distance = pairs.iter().map(|pair| pair.2).min().unwrap();
+        let max_distance = pairs.iter().map(|pair| pair.2).max().unwrap();
+
+        assert_eq!(
+            distances.sum(),
+            Some(pairs.iter().map(|pair| pair.2).sum::<u64>())
+        );
+        assert_eq!(distances.closest_pair().unwrap().2, min_distance);
+        assert_eq!(distances.farthest_pair().unwrap().2, max_distance);
+        assert_eq!(distances.histogram().values().sum::<usize>(), pairs.len());
+        let report = galaxy_statistics(&universe, 2).unwrap();
+        assert!(report.contains("Sum of distances: 374\n"));
+        assert!(report.contains("\n9: "));
+        assert!(GalaxyDistances::new(vec![(0, 0), (u64::MAX, 1)]).is_err());
+        // Galaxies 5 and 9 from the puzzle text are 9 steps apart
+        assert_eq!(distances.distance(4, 8), 9);
+        for (galaxy, nearest) in distances.nearest_neighbours().iter().enumerate() {
+            let expected = (0..n)
+                .filter(|&other| other != galaxy)
+                .map(|other| distances.distance(galaxy, other))
+                .min();
+            assert_eq!(nearest.map(|(_, distance)| distance), expected);
+        }
+    }
+
+    #[test]
+    fn check_render() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let space = process_input(&input);
+        let universe = Universe::new(&space);
+
+        // Rows 3 and 7, cols 2, 5 and 8 are empty
+        let marked_space = universe.mark_empty_space(&space);
+        let marked_cells = (0..space.rows)
+            .flat_map(|row| (0..space.cols).map(move |col| (row, col)))
+            .filter(|&(row, col)| *marked_space.at(row, col) == '*')
+            .count();
+        assert_eq!(marked_cells, 2 * 10 + 3 * 10 - 2 * 3);
+
+        let svg = render_svg(&marked_space, &RenderOptions::default(), space_style);
+        assert_eq!(svg.matches("<circle").count(), universe.galaxies.len());
+        assert_eq!(svg.matches("<rect").count(), 1 + marked_cells);
+    }
 }
@@ -1,37 +1,45 @@
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::{Flag, SolverIo};
 mod part1;
-use part1::{
-    compute_distance_between_galaxies, compute_manhattan_distance_between, expand_universe,
-    get_galaxies_coordinates, process_input,
-};
+use lib_utils::metric::Manhattan;
+use part1::{compute_distance_between_galaxies, galaxy_statistics, process_input, Universe};
+
+const EXPANSION_FACTOR: u64 = 100;
+// const EXPANSION_FACTOR: u64 = 1000000;
 
 fn advent_of_code(input_text: &str) -> String {
     let input: String = String::from(input_text);
-    let mut space = process_input(&input);
-    expand_universe(&mut space);
-    // Substract 1 beacuse i am adding the row/col, not replacing it
-    let factor = 100 - 1;
-    // let factor = 1000000 - 1;
-    let distance = compute_distance_between_galaxies(&space, &factor);
-    return distance.to_string();
+    let space = process_input(&input);
+    let universe = Universe::new(&space);
+    match compute_distance_between_galaxies(&universe, EXPANSION_FACTOR, &Manhattan) {
+        Ok(distance) => distance.to_string(),
+        Err(error) => panic!("{}", error),
+    }
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(
+        env!("CARGO_MANIFEST_DIR"),
+        "part2",
+        &[Flag::switch("--stats")],
+    );
+    let input: String = io.content();
 
     // Process data
     let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
+
+    // Distance statistics with "--stats"
+    if io.has_flag("--stats") {
+        let universe = Universe::new(&process_input(&input));
+        match galaxy_statistics(&universe, EXPANSION_FACTOR) {
+            Ok(report) => eprint!("{}", report),
+            Err(error) => eprintln!("{}", error),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,173 +1,983 @@
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::{One, Zero};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::collections::HashMap;
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use std::ops::Range;
+use test_utils::{CliOption, Flag, SolverIo};
 
-pub struct SpringsMap {
-    pub cfg: String,
-    pub groups: Vec<u8>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnfoldSpec {
+    pub factor: usize,
+    pub separator: char,
+    pub repeat_groups: bool,
 }
 
-impl SpringsMap {
-    fn extend_line(input_line: &str, factor: u8) -> String {
-        let mut extended_line = String::new();
+impl Default for UnfoldSpec {
+    fn default() -> Self {
+        UnfoldSpec {
+            factor: 1,
+            separator: '?',
+            repeat_groups: true,
+        }
+    }
+}
 
-        let mut parts = input_line.split(' ');
-        if let (Some(cfg), Some(groups)) = (parts.next(), parts.last()) {
-            let mut extended_cfg = String::from(cfg);
-            let mut extended_groups = String::from(groups);
+impl UnfoldSpec {
+    pub fn new(factor: usize) -> UnfoldSpec {
+        UnfoldSpec {
+            factor,
+            ..Default::default()
+        }
+    }
+
+    // "--unfold <factor>", "--separator <char>" and "--no-repeat-groups"
+    pub const FLAGS: &'static [Flag] = &[
+        Flag::value("--unfold").changing_answer(),
+        Flag::value("--separator").changing_answer(),
+        Flag::switch("--no-repeat-groups").changing_answer(),
+    ];
+
+    pub fn from_options(options: &[CliOption], default_factor: usize) -> UnfoldSpec {
+        let mut unfold = UnfoldSpec::new(default_factor);
+        for option in options {
+            let value: &str = &option.value;
+            match option.flag.name {
+                "--unfold" => {
+                    unfold.factor = value
+                        .parse::<usize>()
+                        .unwrap_or_else(|_| panic!("Invalid unfold factor: {}", value));
+                }
+                "--separator" => {
+                    unfold.separator = match value {
+                        "." | "#" | "?" => value.chars().next().unwrap(),
+                        _ => panic!("Invalid separator: {}", value),
+                    };
+                }
+                "--no-repeat-groups" => unfold.repeat_groups = false,
+                _ => (),
+            }
+        }
+        unfold
+    }
+}
+
+// Concrete arrangements of each row to inspect or to use as test data: every
+// one with "--arrangements", or "--sample <n>" random ones, the same ones for
+// the same "--seed <s>"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ArrangementsDump {
+    pub all: bool,
+    pub samples: usize,
+    pub seed: Option<u64>,
+}
 
-            for _ in 1..factor {
-                extended_cfg.push('?');
-                extended_cfg.push_str(cfg);
+impl ArrangementsDump {
+    pub const FLAGS: &'static [Flag] = &[
+        Flag::switch("--arrangements"),
+        Flag::value("--sample"),
+        Flag::value("--seed"),
+    ];
 
-                extended_groups.push(',');
-                extended_groups.push_str(groups);
+    pub fn from_options(options: &[CliOption]) -> ArrangementsDump {
+        let mut dump = ArrangementsDump::default();
+        for option in options {
+            let value: &str = &option.value;
+            match option.flag.name {
+                "--arrangements" => dump.all = true,
+                "--sample" => {
+                    dump.samples = value
+                        .parse::<usize>()
+                        .unwrap_or_else(|_| panic!("Invalid number of samples: {}", value));
+                }
+                "--seed" => {
+                    let seed = value
+                        .parse::<u64>()
+                        .unwrap_or_else(|_| panic!("Invalid seed: {}", value));
+                    dump.seed = Some(seed);
+                }
+                _ => (),
             }
+        }
+        dump
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.all || self.samples > 0
+    }
 
-            extended_line.push_str(&extended_cfg);
-            extended_line.push(' ');
-            extended_line.push_str(&extended_groups);
+    pub fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
         }
+    }
 
-        extended_line
+    // Arrangements of the unfolded row written as rows without '?', so each
+    // one is a valid input line with a single arrangement
+    pub fn rows<R: Rng + ?Sized>(&self, sm: &SpringsMap, rng: &mut R) -> Vec<String> {
+        let table = sm.arrangements_table();
+        let groups: Vec<String> = sm
+            .unfolded_groups()
+            .iter()
+            .map(|group| group.to_string())
+            .collect();
+        let groups: String = groups.join(",");
+
+        let mut springs: Vec<String> = Vec::new();
+        if self.all {
+            springs.extend(table.arrangements());
+        }
+        if !table.count().is_zero() {
+            springs.extend((0..self.samples).flat_map(|_| table.sample(rng)));
+        }
+        springs
+            .iter()
+            .map(|springs| format!("{} {}", springs, groups))
+            .collect()
     }
+}
 
-    fn new(input_line: &str, factor: u8) -> SpringsMap {
-        let input_line_extended = SpringsMap::extend_line(input_line, factor);
-        let mut parts = input_line_extended.split(' ');
+pub struct SpringsMap {
+    // Folded row, as read from the input
+    pub cfg: String,
+    pub groups: Vec<u8>,
+    pub unfold: UnfoldSpec,
+}
+
+impl SpringsMap {
+    pub fn parse(input_line: &str, unfold: &UnfoldSpec) -> Result<SpringsMap, String> {
+        let mut parts = input_line.split(' ');
         if let (Some(cfg), Some(groups)) = (parts.next(), parts.last()) {
             let mut groups_vec: Vec<u8> = Vec::new();
             for group in groups.split(',') {
-                groups_vec.push(group.parse::<u8>().unwrap());
+                match group.parse::<u8>() {
+                    Ok(group) => groups_vec.push(group),
+                    Err(_) => return Err(format!("Invalid group {} in: {}", group, input_line)),
+                }
             }
             let cfg = cfg.to_string();
-            SpringsMap {
+            Ok(SpringsMap {
                 cfg,
                 groups: groups_vec,
-            }
+                unfold: *unfold,
+            })
         } else {
-            panic!("Invalid input line!");
+            Err(format!("Invalid input line: {}", input_line))
+        }
+    }
+
+    #[cfg_attr(not(test), allow(unused))]
+    fn new(input_line: &str, unfold: &UnfoldSpec) -> SpringsMap {
+        SpringsMap::parse(input_line, unfold).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn unfolded_cfg(&self) -> String {
+        let separator = self.unfold.separator.to_string();
+        vec![self.cfg.as_str(); self.unfold.factor].join(&separator)
+    }
+
+    pub fn unfolded_groups(&self) -> Vec<u8> {
+        if self.unfold.factor == 0 && self.unfold.repeat_groups {
+            return Vec::new();
         }
+        let copies = match self.unfold.repeat_groups {
+            true => self.unfold.factor,
+            false => 1,
+        };
+        self.groups.repeat(copies)
     }
+
+    pub fn count_arrangements(&self) -> Count {
+        count_unfolded_arrangements(self.cfg.as_bytes(), &self.groups, &self.unfold)
+    }
+
+    pub fn arrangements_table(&self) -> ArrangementsTable {
+        ArrangementsTable::new(self.unfolded_cfg().into_bytes(), self.unfolded_groups())
+    }
+}
+
+#[cfg_attr(not(test), allow(unused))]
+pub fn process_input(input: &str, unfold: &UnfoldSpec) -> Vec<SpringsMap> {
+    process_lines(input.lines(), unfold)
+        .map(|sm| sm.unwrap_or_else(|error| panic!("{}", error)))
+        .collect()
+}
+
+// Each row is counted on its own, so rows are parsed as they are read
+pub fn process_lines<'a, I>(
+    lines: I,
+    unfold: &'a UnfoldSpec,
+) -> impl Iterator<Item = Result<SpringsMap, String>> + 'a
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+    I::IntoIter: 'a,
+{
+    lines
+        .into_iter()
+        .map(move |line| SpringsMap::parse(line.as_ref(), unfold))
+}
+
+pub struct ArrangementsTable {
+    cfg: Vec<u8>,
+    groups: Vec<u8>,
+    // Number of '.' before each position, to check a group window in O(1)
+    dots_before: Vec<usize>,
+    // counts[position * width + group_index] counts the arrangements of
+    // cfg[position..] using groups[group_index..]
+    counts: Vec<Count>,
 }
 
-pub fn process_input(input: &str, factor: u8) -> Vec<SpringsMap> {
-    let mut springs_map: Vec<SpringsMap> = Vec::new();
+impl ArrangementsTable {
+    pub fn new(cfg: Vec<u8>, groups: Vec<u8>) -> ArrangementsTable {
+        let mut dots_before: Vec<usize> = Vec::with_capacity(cfg.len() + 1);
+        dots_before.push(0);
+        for (position, &spring) in cfg.iter().enumerate() {
+            dots_before.push(dots_before[position] + (spring == b'.') as usize);
+        }
+
+        let num_positions = cfg.len();
+        let width = groups.len() + 1;
+        let mut counts: Vec<Count> = vec![Count::zero(); (num_positions + 1) * width];
+        counts[num_positions * width + groups.len()] = Count::one();
+        let mut table = ArrangementsTable {
+            cfg,
+            groups,
+            dots_before,
+            counts,
+        };
 
-    for line in input.lines() {
-        springs_map.push(SpringsMap::new(line, factor));
+        for position in (0..num_positions).rev() {
+            for group_index in (0..width).rev() {
+                let count: Count = table
+                    .transitions(position, group_index)
+                    .into_iter()
+                    .flatten()
+                    .map(|(next_position, next_group)| table.count_from(next_position, next_group))
+                    .sum();
+                table.counts[position * width + group_index] = count;
+            }
+        }
+        table
+    }
+
+    pub fn count(&self) -> &Count {
+        self.count_from(0, 0)
+    }
+
+    pub fn count_from(&self, position: usize, group_index: usize) -> &Count {
+        &self.counts[position * (self.groups.len() + 1) + group_index]
+    }
+
+    // States reachable from (position, group_index): evaluating the spring
+    // as '.', or as the start of the next group of '#'
+    fn transitions(&self, position: usize, group_index: usize) -> [Option<(usize, usize)>; 2] {
+        let num_positions = self.cfg.len();
+        let spring = self.cfg[position];
+        let mut transitions = [None, None];
+
+        if spring != b'#' {
+            transitions[0] = Some((position + 1, group_index));
+        }
+
+        // The whole group must fit without any '.' and must not be followed by a '#'
+        if spring != b'.' && group_index < self.groups.len() {
+            let group_end = position + self.groups[group_index] as usize;
+            if group_end <= num_positions
+                && self.dots_before[group_end] == self.dots_before[position]
+                && (group_end == num_positions || self.cfg[group_end] != b'#')
+            {
+                let next_position = (group_end + 1).min(num_positions);
+                transitions[1] = Some((next_position, group_index + 1));
+            }
+        }
+        transitions
+    }
+
+    // Concrete springs written when moving from one state to the next
+    fn push_springs(&self, springs: &mut Vec<u8>, state: (usize, usize), next: (usize, usize)) {
+        let mut damaged = 0;
+        if next.1 > state.1 {
+            damaged = self.groups[state.1] as usize;
+        }
+        springs.extend(std::iter::repeat_n(b'#', damaged));
+        springs.extend(std::iter::repeat_n(b'.', next.0 - state.0 - damaged));
+    }
+
+    // Lazily walks every valid arrangement, skipping states with no solutions
+    pub fn arrangements(&self) -> Arrangements<'_> {
+        let mut stack: Vec<(usize, usize, Vec<u8>)> = Vec::new();
+        if !self.count().is_zero() {
+            stack.push((0, 0, Vec::with_capacity(self.cfg.len())));
+        }
+        Arrangements { table: self, stack }
+    }
+
+    // Picks each transition with probability proportional to its count, so
+    // every valid arrangement is equally likely. None without any arrangement
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<String> {
+        if self.count().is_zero() {
+            return None;
+        }
+        let mut springs: Vec<u8> = Vec::with_capacity(self.cfg.len());
+        let mut state: (usize, usize) = (0, 0);
+        while state.0 < self.cfg.len() {
+            let mut pick: Count = rng.gen_biguint_below(self.count_from(state.0, state.1));
+            for next in self.transitions(state.0, state.1).into_iter().flatten() {
+                let count = self.count_from(next.0, next.1);
+                if pick < *count {
+                    self.push_springs(&mut springs, state, next);
+                    state = next;
+                    break;
+                }
+                pick -= count;
+            }
+        }
+        Some(String::from_utf8(springs).unwrap())
     }
-    springs_map
 }
 
-pub fn count_valid_arrangements(
-    cfg: &str,
-    groups: &Vec<u8>,
-    cache: &mut HashMap<(String, Vec<u8>), u64>,
-) -> u64 {
-    let mut cont = 0;
+pub struct Arrangements<'a> {
+    table: &'a ArrangementsTable,
+    // Partial arrangements still to complete: (position, group_index, springs)
+    stack: Vec<(usize, usize, Vec<u8>)>,
+}
 
-    if cfg.is_empty() {
-        if groups.is_empty() {
-            // Is valid
-            cont += 1;
+impl Iterator for Arrangements<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while let Some((position, group_index, springs)) = self.stack.pop() {
+            if position == self.table.cfg.len() {
+                return Some(String::from_utf8(springs).unwrap());
+            }
+            // Pushed in reverse so that '.' is explored first
+            let transitions = self.table.transitions(position, group_index);
+            for next in transitions.into_iter().flatten().rev() {
+                if !self.table.count_from(next.0, next.1).is_zero() {
+                    let mut next_springs = springs.clone();
+                    self.table
+                        .push_springs(&mut next_springs, (position, group_index), next);
+                    self.stack.push((next.0, next.1, next_springs));
+                }
+            }
         }
-        return cont;
+        None
     }
+}
+
+pub fn count_valid_arrangements(cfg: &[u8], groups: &[u8]) -> Count {
+    ArrangementsTable::new(cfg.to_vec(), groups.to_vec())
+        .count()
+        .clone()
+}
 
-    if groups.is_empty() {
-        if !cfg.contains("#") {
-            // Is valid
-            cont += 1;
+// Arrangement counts grow exponentially with the unfold factor, so they are
+// kept exact in arbitrary precision
+pub type Count = BigUint;
+
+// State at the boundary between two copies: (offset, group). The offset
+// counts the springs of the next copy already used by a group crossing the
+// boundary, its '#' and the '.' after it, and group is the next one to place
+type BoundaryState = (usize, usize);
+
+// Ways to go through a whole copy, placing some groups, from one boundary
+// state to the next one
+struct BlockTransfer {
+    from: BoundaryState,
+    offset: usize,
+    placed: usize,
+    ways: Count,
+}
+
+// Groups to build the tables of a copy from, and the indices where placing
+// can stop. Repeated groups are unrolled far enough for a single copy, and
+// only their index modulo the number of groups matters
+fn unrolled_groups(groups: &[u8], period: usize, cyclic: bool) -> (Vec<u8>, Range<usize>) {
+    if !cyclic {
+        return (groups.to_vec(), 0..groups.len() + 1);
+    }
+    // A copy places at most one group every two springs, plus a crossing one
+    let max_len = *groups.iter().max().unwrap() as usize;
+    let repeats = (period + max_len) / 2 / groups.len() + 2;
+    let ends = (repeats - 1) * groups.len()..repeats * groups.len();
+    (groups.repeat(repeats), ends)
+}
+
+// Transfers through a copy of `block` followed by copies of `next_block`.
+// A group crossing the boundary is placed by the copy it starts in, and the
+// '.' after it is checked by the next copy
+fn block_transfers(
+    block: &[u8],
+    next_block: &[u8],
+    groups: &[u8],
+    cyclic: bool,
+) -> Vec<BlockTransfer> {
+    let period = block.len();
+    let spring = |position: usize| match position < period {
+        true => block[position],
+        false => next_block[position % period],
+    };
+    let max_len = groups.iter().max().map_or(0, |&len| len as usize);
+    let group_of = |index: usize| match cyclic {
+        true => index % groups.len(),
+        false => index,
+    };
+    let (unrolled, ends) = unrolled_groups(groups, period, cyclic);
+    let mut transfers: Vec<BlockTransfer> = Vec::new();
+
+    // Every transfer read from a table of the springs before a '.' or before
+    // the group crossing the boundary, which ends at `end`
+    let table_transfers = |table: &ArrangementsTable, offset: usize, end: usize| {
+        let mut transfers: Vec<BlockTransfer> = Vec::new();
+        for from_offset in 0..=max_len.min(table.cfg.len()) {
+            for group_index in 0..=table.groups.len() {
+                let ways = table.count_from(from_offset, group_index);
+                if !ways.is_zero() {
+                    transfers.push(BlockTransfer {
+                        from: (from_offset, group_of(group_index)),
+                        offset,
+                        placed: end - group_index,
+                        ways: ways.clone(),
+                    });
+                }
+            }
+        }
+        transfers
+    };
+
+    for end in ends {
+        // No group crosses the boundary, so the last spring is a '.'
+        if spring(period - 1) != b'#' {
+            let cfg = block[..period - 1].to_vec();
+            let table = ArrangementsTable::new(cfg, unrolled[..end].to_vec());
+            transfers.extend(table_transfers(&table, 0, end));
+        }
+        if end == 0 {
+            continue;
+        }
+        // The last group placed starts at `start` and crosses the boundary
+        let len = unrolled[end - 1] as usize;
+        for offset in 1..=len {
+            if period + offset < len + 1 {
+                continue;
+            }
+            let start = period + offset - 1 - len;
+            if (start..start + len).any(|position| spring(position) == b'.') {
+                continue;
+            }
+            if start <= max_len {
+                transfers.push(BlockTransfer {
+                    from: (start, group_of(end - 1)),
+                    offset,
+                    placed: 1,
+                    ways: Count::one(),
+                });
+            }
+            if start > 0 && spring(start - 1) != b'#' {
+                let cfg = block[..start - 1].to_vec();
+                let table = ArrangementsTable::new(cfg, unrolled[..end - 1].to_vec());
+                transfers.extend(table_transfers(&table, offset, end));
+            }
         }
-        return cont;
     }
 
-    // Check cache
-    let key = (cfg.to_string(), groups.clone());
-    if let Some(value) = cache.get(&key) {
-        return *value;
+    // A group longer than the copy can cross it entirely
+    let num_groups = match cyclic {
+        true => groups.len(),
+        false => groups.len() + 1,
+    };
+    for from_offset in period..=max_len {
+        for group in 0..num_groups {
+            transfers.push(BlockTransfer {
+                from: (from_offset, group),
+                offset: from_offset - period,
+                placed: 0,
+                ways: Count::one(),
+            });
+        }
     }
 
-    let first_char = cfg.chars().next().unwrap();
+    // The spring right after a crossing group must be a '.'
+    transfers.retain(|transfer| {
+        let from_offset = transfer.from.0;
+        from_offset == 0 || from_offset > period || block[from_offset - 1] != b'#'
+    });
+    transfers
+}
 
-    // Evaluating '?' as '.'
-    if first_char == '.' || first_char == '?' {
-        let rest_cfg = &cfg[1..];
-        cont += count_valid_arrangements(rest_cfg, groups, cache);
+// Ways to end the row within a last copy of `block`, from each offset and
+// number of groups left to place
+fn final_ways(block: &[u8], groups: &[u8], cyclic: bool) -> impl Fn(usize, usize) -> Count {
+    let period = block.len();
+    let (unrolled, _) = unrolled_groups(groups, period, cyclic);
+    let num_groups = unrolled.len();
+    let table = ArrangementsTable::new(block.to_vec(), unrolled);
+    let crossing_ok = |offset: usize| offset == 0 || block[offset - 1] != b'#';
+    let crossing_ok: Vec<bool> = (0..=period).map(crossing_ok).collect();
+    move |offset: usize, groups_left: usize| {
+        if groups_left > num_groups {
+            return Count::zero();
+        }
+        match offset {
+            // The '.' after a group ending the row is not part of it
+            _ if offset == period + 1 => Count::from((groups_left == 0) as u8),
+            _ if offset > period || !crossing_ok[offset] => Count::zero(),
+            _ => table.count_from(offset, num_groups - groups_left).clone(),
+        }
     }
+}
 
-    // Evaluating '?' as '#'
-    if first_char == '#' || first_char == '?' {
-        // Check if can be valid configuration
-        // If group[0] is greater than cfg.len(), then is not valid
-        // If group_cfg contais '.', then is not valid
-        // If cfg[0..group[0] + 1] i '#', then is not valid
+// Counts the arrangements of the unfolded row without building it. Each copy
+// after the first one is the separator followed by the folded springs, so
+// the transfer between boundary states is computed once from the tables and
+// reused for every copy
+pub fn count_unfolded_arrangements(cfg: &[u8], groups: &[u8], unfold: &UnfoldSpec) -> Count {
+    match unfold.factor {
+        0 => return Count::from((unfold.repeat_groups || groups.is_empty()) as u8),
+        1 => return count_valid_arrangements(cfg, groups),
+        _ => (),
+    }
+    // A '.' before the row changes nothing, and makes the first copy a block
+    let first_block: Vec<u8> = [b".", cfg].concat();
+    let block: Vec<u8> = [&[unfold.separator as u8], cfg].concat();
+
+    if unfold.repeat_groups && !groups.is_empty() {
+        count_with_repeated_groups(&first_block, &block, groups, unfold.factor)
+    } else {
+        count_with_fixed_groups(&first_block, &block, groups, unfold.factor)
+    }
+}
 
-        let first_group = groups[0];
-        if cfg.len() as u8 >= first_group {
-            let group_cfg = &cfg[..first_group as usize];
-            if !group_cfg.contains('.') {
-                let rest_cfg = &cfg[first_group as usize..];
-                let mut rest_groups = groups.clone();
-                rest_groups.remove(0);
+// The groups do not grow with the factor, so the boundary states form a
+// fixed square matrix that is raised to the number of copies by repeated
+// squaring
+fn count_with_fixed_groups(
+    first_block: &[u8],
+    block: &[u8],
+    groups: &[u8],
+    factor: usize,
+) -> Count {
+    let width = groups.iter().max().map_or(0, |&len| len as usize) + 1;
+    let num_states = (groups.len() + 1) * width;
+    let index = |(offset, group): BoundaryState| group * width + offset;
 
-                if rest_cfg.is_empty() {
-                    cont += count_valid_arrangements(rest_cfg, &rest_groups, cache);
+    let mut transfer: Vec<Vec<Count>> = vec![vec![Count::zero(); num_states]; num_states];
+    for step in block_transfers(block, block, groups, false) {
+        let next_state = (step.offset, step.from.1 + step.placed);
+        transfer[index(step.from)][index(next_state)] = step.ways;
+    }
+    let transfer = matrix_power(&transfer, factor - 2);
+
+    let final_ways = final_ways(block, groups, false);
+    let mut count = Count::zero();
+    for first in block_transfers(first_block, block, groups, false) {
+        if first.from != (0, 0) {
+            continue;
+        }
+        let start = index((first.offset, first.placed));
+        for group in 0..=groups.len() {
+            for offset in 0..width {
+                let ways = &transfer[start][index((offset, group))];
+                if !ways.is_zero() {
+                    count += &first.ways * ways * final_ways(offset, groups.len() - group);
+                }
+            }
+        }
+    }
+    count
+}
+
+// Boundary state of a repeated row: (offset, lag), where the lag is the
+// number of groups placed minus the groups of the copies gone through
+type LagState = (usize, isize);
+
+// Lag states beyond which the copies are applied one at a time, the matrix
+// power costs the cube of their number
+const MAX_LAG_STATES: usize = 64;
+
+// The groups grow with the factor, so the groups placed can drift away from
+// any fixed number per copy. The transfers only depend on the lag modulo the
+// number of groups, so when the lags reached stay within a fixed set the lag
+// states form a square matrix raised to the number of copies. When they do
+// not there is no fixed matrix: with "?? 1" and '?' between copies there are
+// C(2n, n) arrangements of n copies, which no fixed matrix power produces, and
+// the copies are applied one at a time instead
+fn count_with_repeated_groups(
+    first_block: &[u8],
+    block: &[u8],
+    groups: &[u8],
+    factor: usize,
+) -> Count {
+    let width = *groups.iter().max().unwrap() as usize + 1;
+    let period = groups.len() as isize;
+    let index = |(offset, group): BoundaryState| group * width + offset;
+
+    // transfers[index((offset, group))] lists (offset, groups placed, ways)
+    let mut transfers: Vec<Vec<(usize, usize, Count)>> = vec![Vec::new(); groups.len() * width];
+    for step in block_transfers(block, block, groups, true) {
+        transfers[index(step.from)].push((step.offset, step.placed, step.ways));
+    }
+    let final_ways = final_ways(block, groups, true);
+    let firsts: Vec<BlockTransfer> = block_transfers(first_block, block, groups, true)
+        .into_iter()
+        .filter(|first| first.from == (0, 0))
+        .collect();
+
+    // Groups a single copy can place, to drop states that can no longer reach
+    // the end. The last copy places between 0 and `final_placed` groups
+    let placed = transfers.iter().flatten().map(|&(_, placed, _)| placed);
+    let (min_placed, max_placed) = (placed.clone().min(), placed.max());
+    let final_placed = (0..width)
+        .flat_map(|offset| (0..=groups.len() * factor).map(move |left| (offset, left)))
+        .filter(|&(offset, left)| !final_ways(offset, left).is_zero())
+        .map(|(_, left)| left)
+        .max()
+        .unwrap_or(0);
+    // Once the lag only falls or only rises, the lags past the ones the last
+    // copy can end from never reach the end
+    let is_dead = |(_, lag): LagState| {
+        (max_placed.unwrap_or(0) as isize <= period && lag < period - final_placed as isize)
+            || (min_placed.unwrap_or(0) as isize >= period && lag > period)
+    };
+    let next_states = |(offset, lag): LagState| {
+        transfers[index((offset, lag.rem_euclid(period) as usize))]
+            .iter()
+            .map(move |(next_offset, placed, ways)| {
+                ((*next_offset, lag + *placed as isize - period), ways)
+            })
+    };
+
+    // Every lag state reached after the first copy, unless there are too many
+    let mut lag_states: Vec<LagState> = firsts
+        .iter()
+        .map(|first| (first.offset, first.placed as isize - period))
+        .filter(|&state| !is_dead(state))
+        .collect();
+    lag_states.sort();
+    lag_states.dedup();
+    let mut state_index: HashMap<LagState, usize> = lag_states
+        .iter()
+        .enumerate()
+        .map(|(index, &state)| (state, index))
+        .collect();
+    let mut visited = 0;
+    while visited < lag_states.len() && lag_states.len() <= MAX_LAG_STATES {
+        for (next, _) in next_states(lag_states[visited]) {
+            if !is_dead(next) && !state_index.contains_key(&next) {
+                state_index.insert(next, lag_states.len());
+                lag_states.push(next);
+            }
+        }
+        visited += 1;
+    }
+    if lag_states.len() > MAX_LAG_STATES {
+        return count_copy_by_copy(
+            &firsts,
+            &transfers,
+            &final_ways,
+            width,
+            groups.len(),
+            factor,
+        );
+    }
+
+    let num_states = lag_states.len();
+    let mut transfer: Vec<Vec<Count>> = vec![vec![Count::zero(); num_states]; num_states];
+    for (from, &state) in lag_states.iter().enumerate() {
+        for (next, ways) in next_states(state) {
+            if let Some(&to) = state_index.get(&next) {
+                transfer[from][to] += ways;
+            }
+        }
+    }
+    let transfer = matrix_power(&transfer, factor - 2);
+
+    // The last copy places the groups left, one copy worth plus the lag
+    let final_counts: Vec<Count> = lag_states
+        .iter()
+        .map(|&(offset, lag)| match usize::try_from(period - lag) {
+            Ok(groups_left) => final_ways(offset, groups_left),
+            Err(_) => Count::zero(),
+        })
+        .collect();
+    let mut count = Count::zero();
+    for first in firsts.iter() {
+        let state = (first.offset, first.placed as isize - period);
+        if let Some(&from) = state_index.get(&state) {
+            for (ways, final_count) in transfer[from].iter().zip(final_counts.iter()) {
+                if !ways.is_zero() && !final_count.is_zero() {
+                    count += &first.ways * ways * final_count;
+                }
+            }
+        }
+    }
+    count
+}
+
+// Applies the copies of a row with repeated groups one at a time, with the
+// number of groups placed so far in the state
+fn count_copy_by_copy(
+    firsts: &[BlockTransfer],
+    transfers: &[Vec<(usize, usize, Count)>],
+    final_ways: &impl Fn(usize, usize) -> Count,
+    width: usize,
+    num_groups: usize,
+    factor: usize,
+) -> Count {
+    let total_groups = num_groups * factor;
+    let index = |(offset, group): BoundaryState| group * width + offset;
+
+    // Groups a single copy can place, to drop states that can no longer
+    // reach the end with the copies left
+    let max_placed = transfers
+        .iter()
+        .flatten()
+        .map(|&(_, placed, _)| placed)
+        .max()
+        .unwrap_or(0);
+    let final_placed = (0..width)
+        .flat_map(|offset| (0..=total_groups).map(move |left| (offset, left)))
+        .filter(|&(offset, left)| !final_ways(offset, left).is_zero())
+        .map(|(_, left)| left)
+        .max()
+        .unwrap_or(0);
+
+    let mut states: Vec<Count> = vec![Count::zero(); (total_groups + 1) * width];
+    for first in firsts {
+        if first.placed <= total_groups {
+            states[index((first.offset, first.placed))] += &first.ways;
+        }
+    }
+    // Only the states between the first and last non zero counts are visited
+    let mut live = 0..states.len();
+    let mut next_states: Vec<Count> = vec![Count::zero(); states.len()];
+    let mut product = Count::zero();
+    for copy in 1..factor - 1 {
+        let reachable =
+            total_groups.saturating_sub((factor - copy - 2) * max_placed + final_placed);
+        let mut next_live = states.len()..0;
+        for state_index in live {
+            if states[state_index].is_zero() {
+                continue;
+            }
+            let count = &states[state_index];
+            let (group, offset) = (state_index / width, state_index % width);
+            for (next_offset, placed, ways) in &transfers[index((offset, group % num_groups))] {
+                let next_group = group + placed;
+                if next_group < reachable || next_group > total_groups {
+                    continue;
+                }
+                let next_index = index((*next_offset, next_group));
+                if ways.is_one() {
+                    next_states[next_index] += count;
                 } else {
-                    let next_cfg_char = rest_cfg.chars().next().unwrap();
-                    if next_cfg_char != '#' {
-                        let rest_cfg = &rest_cfg[1..];
-                        cont += count_valid_arrangements(rest_cfg, &rest_groups, cache);
-                    }
+                    // Reuses the buffers instead of allocating a product per transfer
+                    product.clone_from(count);
+                    product *= ways;
+                    next_states[next_index] += &product;
                 }
+                next_live.start = next_live.start.min(next_index);
+                next_live.end = next_live.end.max(next_index + 1);
             }
+            states[state_index].set_zero();
         }
+        live = next_live;
+        std::mem::swap(&mut states, &mut next_states);
     }
 
-    // Add to cache
-    cache.insert(key, cont);
+    let mut count = Count::zero();
+    for state_index in live {
+        let (group, offset) = (state_index / width, state_index % width);
+        if !states[state_index].is_zero() {
+            count += &states[state_index] * final_ways(offset, total_groups - group);
+        }
+    }
+    count
+}
 
-    return cont;
+fn matrix_multiply(a: &[Vec<Count>], b: &[Vec<Count>]) -> Vec<Vec<Count>> {
+    let size = a.len();
+    let mut product: Vec<Vec<Count>> = vec![vec![Count::zero(); size]; size];
+    for row in 0..size {
+        for k in 0..size {
+            if a[row][k].is_zero() {
+                continue;
+            }
+            for col in 0..size {
+                if !b[k][col].is_zero() {
+                    product[row][col] += &a[row][k] * &b[k][col];
+                }
+            }
+        }
+    }
+    product
 }
 
-fn advent_of_code(input_text: &str) -> String {
-    let mut valid_arrangements_sum = 0;
+fn matrix_power(matrix: &[Vec<Count>], mut exponent: usize) -> Vec<Vec<Count>> {
+    let size = matrix.len();
+    let mut result: Vec<Vec<Count>> = vec![vec![Count::zero(); size]; size];
+    for (diagonal, row) in result.iter_mut().enumerate() {
+        row[diagonal] = Count::one();
+    }
+    let mut base: Vec<Vec<Count>> = matrix.to_vec();
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = matrix_multiply(&result, &base);
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = matrix_multiply(&base, &base);
+        }
+    }
+    result
+}
+
+// Sums the arrangements of every row, failing on the first invalid row
+pub fn sum_arrangements<I>(lines: I, unfold: &UnfoldSpec) -> Result<Count, String>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    sum_rows_arrangements(process_lines(lines, unfold))
+}
 
-    let input: String = String::from(input_text);
-    let springs_map = process_input(&input, 1);
+pub fn sum_rows_arrangements(
+    springs_map: impl IntoIterator<Item = Result<SpringsMap, String>>,
+) -> Result<Count, String> {
+    let mut valid_arrangements_sum = Count::zero();
 
     for sm in springs_map {
-        let mut cache = &mut HashMap::new();
-        let groups = sm.groups.clone();
-        let valid_arrangements = count_valid_arrangements(&sm.cfg, &groups, &mut cache);
-        valid_arrangements_sum += valid_arrangements;
+        valid_arrangements_sum += sm?.count_arrangements();
     }
-    return valid_arrangements_sum.to_string();
+    Ok(valid_arrangements_sum)
+}
+
+// Prints the dumped arrangements of each row as it goes through
+pub fn dump_rows<'a, R: Rng + ?Sized>(
+    rows: impl Iterator<Item = Result<SpringsMap, String>> + 'a,
+    dump: &'a ArrangementsDump,
+    rng: &'a mut R,
+) -> impl Iterator<Item = Result<SpringsMap, String>> + 'a {
+    rows.inspect(move |sm| {
+        if let (true, Ok(sm)) = (dump.is_enabled(), sm) {
+            for row in dump.rows(sm, rng) {
+                println!("{}", row);
+            }
+        }
+    })
 }
 
 #[cfg_attr(not(test), allow(unused))]
-fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
+fn advent_of_code(input_text: &str) -> String {
+    match sum_arrangements(input_text.lines(), &UnfoldSpec::new(1)) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => panic!("{}", error),
     }
+}
 
-    // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+#[cfg_attr(not(test), allow(unused))]
+fn main() {
+    // Stream input lines from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part1", ArrangementsDump::FLAGS);
+    let lines = io.lines();
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Process data, printing the arrangements of each row with the dump flags
+    let unfold = UnfoldSpec::new(1);
+    let dump = ArrangementsDump::from_options(&io.options);
+    let mut rng = dump.rng();
+    let rows = dump_rows(process_lines(lines, &unfold), &dump, &mut rng);
+    let output: String = match sum_rows_arrangements(rows) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::{HashMap, HashSet};
     use test_utils::{get_full_path, test_advent_of_code};
 
+    // Previous memoised implementation, kept to check the table based one
+    fn count_valid_arrangements_memo(
+        cfg: &str,
+        groups: &Vec<u8>,
+        cache: &mut HashMap<(String, Vec<u8>), u64>,
+    ) -> u64 {
+        let mut cont = 0;
+
+        if cfg.is_empty() {
+            if groups.is_empty() {
+                // Is valid
+                cont += 1;
+            }
+            return cont;
+        }
+
+        if groups.is_empty() {
+            if !cfg.contains("#") {
+                // Is valid
+                cont += 1;
+            }
+            return cont;
+        }
+
+        // Check cache
+        let key = (cfg.to_string(), groups.clone());
+        if let Some(value) = cache.get(&key) {
+            return *value;
+        }
+
+        let first_char = cfg.chars().next().unwrap();
+
+        // Evaluating '?' as '.'
+        if first_char == '.' || first_char == '?' {
+            let rest_cfg = &cfg[1..];
+            cont += count_valid_arrangements_memo(rest_cfg, groups, cache);
+        }
+
+        // Evaluating '?' as '#'
+        if first_char == '#' || first_char == '?' {
+            // Check if can be valid configuration
+            // If group[0] is greater than cfg.len(), then is not valid
+            // If group_cfg contais '.', then is not valid
+            // If cfg[0..group[0] + 1] i '#', then is not valid
+
+            let first_group = groups[0];
+            if cfg.len() as u8 >= first_group {
+                let group_cfg = &cfg[..first_group as usize];
+                if !group_cfg.contains('.') {
+                    let rest_cfg = &cfg[first_group as usize..];
+                    let mut rest_groups = groups.clone();
+                    rest_groups.remove(0);
+
+                    if rest_cfg.is_empty() {
+                        cont += count_valid_arrangements_memo(rest_cfg, &rest_groups, cache);
+                    } else {
+                        let next_cfg_char = rest_cfg.chars().next().unwrap();
+                        if next_cfg_char != '#' {
+                            let rest_cfg = &rest_cfg[1..];
+                            cont += count_valid_arrangements_memo(rest_cfg, &rest_groups, cache);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Add to cache
+        cache.insert(key, cont);
+
+        return cont;
+    }
+
     #[test]
     fn check_code() {
         let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
@@ -177,4 +987,154 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_against_memoised() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        for factor in 1..=5 {
+            for sm in process_input(&input, &UnfoldSpec::new(factor)) {
+                let (cfg, groups) = (sm.unfolded_cfg(), sm.unfolded_groups());
+                let expected = count_valid_arrangements_memo(&cfg, &groups, &mut HashMap::new());
+                assert_eq!(
+                    count_valid_arrangements(cfg.as_bytes(), &groups),
+                    Count::from(expected)
+                );
+            }
+        }
+    }
+
+    fn get_groups(springs: &str) -> Vec<u8> {
+        springs
+            .split('.')
+            .filter(|group| !group.is_empty())
+            .map(|group| group.len() as u8)
+            .collect()
+    }
+
+    fn matches_cfg(springs: &str, cfg: &str) -> bool {
+        springs.len() == cfg.len()
+            && springs
+                .chars()
+                .zip(cfg.chars())
+                .all(|(spring, known)| known == '?' || spring == known)
+    }
+
+    #[test]
+    fn check_arrangements() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let mut rng = StdRng::seed_from_u64(12);
+        for factor in 1..=2 {
+            for sm in process_input(&input, &UnfoldSpec::new(factor)) {
+                let table = sm.arrangements_table();
+                let arrangements: HashSet<String> = table.arrangements().collect();
+                assert_eq!(&Count::from(arrangements.len()), table.count());
+                for springs in arrangements.iter() {
+                    assert!(matches_cfg(springs, &sm.unfolded_cfg()));
+                    assert_eq!(get_groups(springs), sm.unfolded_groups());
+                }
+                for _ in 0..10 {
+                    let sample = table.sample(&mut rng).unwrap();
+                    assert!(arrangements.contains(&sample));
+                }
+            }
+        }
+
+        let args: Vec<String> = ["part1", "--sample", "3", "--seed", "7", "--arrangements"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let io = SolverIo::from_args(&args, "", "part1", ArrangementsDump::FLAGS).unwrap();
+        let dump = ArrangementsDump::from_options(&io.options);
+        assert_eq!(
+            dump,
+            ArrangementsDump {
+                all: true,
+                samples: 3,
+                seed: Some(7)
+            }
+        );
+
+        // Every arrangement, then the samples, each one a row with a single arrangement
+        let sm = SpringsMap::new("?#?? 2", &UnfoldSpec::new(1));
+        let rows = dump.rows(&sm, &mut dump.rng());
+        assert_eq!(rows[..2], [".##. 2", "##.. 2"]);
+        assert_eq!(rows.len(), 2 + 3);
+        assert_eq!(rows, dump.rows(&sm, &mut dump.rng()));
+        for row in rows {
+            let sum = sum_arrangements([row.as_str()], &UnfoldSpec::new(1));
+            assert_eq!(sum, Ok(Count::one()));
+        }
+        let sm = SpringsMap::new("# 2", &UnfoldSpec::new(1));
+        assert_eq!(dump.rows(&sm, &mut rng), Vec::<String>::new());
+
+        // Rows go through unchanged, and the first invalid one is reported
+        let unfold = UnfoldSpec::new(1);
+        let lines = ["?#?? 2", "???.### 1,1,3", "?#? x", "?#? 1"];
+        let dump = ArrangementsDump::default();
+        let rows = dump_rows(process_lines(&lines[..2], &unfold), &dump, &mut rng);
+        assert_eq!(sum_rows_arrangements(rows), Ok(Count::from(3u8)));
+        let rows = dump_rows(process_lines(lines, &unfold), &dump, &mut rng);
+        assert_eq!(
+            sum_rows_arrangements(rows),
+            Err(String::from("Invalid group x in: ?#? x"))
+        );
+    }
+
+    #[test]
+    fn check_unfold_spec() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        // Groups longer than a copy cross several copies, and with "?? 1" the
+        // lags are not bounded so the copies are applied one at a time
+        let input = format!("{}\n? 3\n#?. 4,1\n?? 1\n.?#? 1,1", input.trim_end());
+        for separator in ['?', '.', '#'] {
+            for repeat_groups in [true, false] {
+                for factor in 0..=4 {
+                    let unfold = UnfoldSpec {
+                        factor,
+                        separator,
+                        repeat_groups,
+                    };
+                    for sm in process_input(&input, &unfold) {
+                        let expected = sm.arrangements_table().count().clone();
+                        assert_eq!(sm.count_arrangements(), expected, "{:?}", unfold);
+                    }
+                }
+            }
+        }
+
+        let args: Vec<String> = ["part2", "--unfold", "1000", "--separator", "."]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let io = SolverIo::from_args(&args, "", "part2", UnfoldSpec::FLAGS).unwrap();
+        let unfold = UnfoldSpec::from_options(&io.options, 5);
+        assert_eq!(unfold.factor, 1000);
+        assert_eq!(unfold.separator, '.');
+        assert!(unfold.repeat_groups);
+
+        // With '.' between copies each copy is independent, so there are 10^1000
+        let sm = SpringsMap::new("?###???????? 3,2,1", &unfold);
+        assert_eq!(sm.count_arrangements(), Count::from(10u8).pow(1000));
+        // With '?' the first copy has 10 arrangements and every other one 15
+        let sm = SpringsMap::new("?###???????? 3,2,1", &UnfoldSpec::new(1000));
+        assert_eq!(
+            sm.count_arrangements(),
+            Count::from(10u8) * Count::from(15u8).pow(999)
+        );
+        // The lags of "?? 1" are not bounded, there are C(2n, n) arrangements
+        let sm = SpringsMap::new("?? 1", &UnfoldSpec::new(30));
+        let central: Count = (1..=30u32).fold(Count::one(), |c, k| c * (30 + k) / k);
+        assert_eq!(sm.count_arrangements(), central);
+        assert_eq!(central, Count::from(118264581564861424u64));
+
+        let unfold = UnfoldSpec {
+            repeat_groups: false,
+            ..unfold
+        };
+        let sm = SpringsMap::new("?? 1", &unfold);
+        assert_eq!(sm.count_arrangements(), Count::from(2 * 1000u32));
+    }
 }
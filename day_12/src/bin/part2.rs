@@ -1,39 +1,39 @@
-use std::collections::HashMap;
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::{Flag, SolverIo};
 mod part1;
-use part1::{count_valid_arrangements, process_input};
+use part1::{
+    dump_rows, process_lines, sum_arrangements, sum_rows_arrangements, ArrangementsDump, UnfoldSpec,
+};
 
+#[cfg_attr(not(test), allow(unused))]
 fn advent_of_code(input_text: &str) -> String {
-    let mut valid_arrangements_sum = 0;
-
-    let input: String = String::from(input_text);
-    let springs_map = process_input(&input, 5);
-
-    for sm in springs_map {
-        let mut cache = &mut HashMap::new();
-        let groups = sm.groups.clone();
-        let valid_arrangements = count_valid_arrangements(&sm.cfg, &groups, &mut cache);
-        valid_arrangements_sum += valid_arrangements;
+    match sum_arrangements(input_text.lines(), &UnfoldSpec::new(5)) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => panic!("{}", error),
     }
-    return valid_arrangements_sum.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
-
-    // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
-
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Stream input lines from a path argument, stdin or the txt file
+    let flags: Vec<Flag> = [UnfoldSpec::FLAGS, ArrangementsDump::FLAGS].concat();
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part2", &flags);
+    let lines = io.lines();
+
+    // Process data, printing the arrangements of each unfolded row with the dump flags
+    let unfold = UnfoldSpec::from_options(&io.options, 5);
+    let dump = ArrangementsDump::from_options(&io.options);
+    let mut rng = dump.rng();
+    let rows = dump_rows(process_lines(lines, &unfold), &dump, &mut rng);
+    let output: String = match sum_rows_arrangements(rows) {
+        Ok(sum) => sum.to_string(),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -1,102 +1,209 @@
-use lib_utils::Matrix;
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::SolverIo;
 
-pub fn process_input(input: &str) -> Vec<Matrix<u8>> {
-    let mut valley: Vec<Matrix<u8>> = Vec::new();
+// Bits packed in 64 bit words, bit i is bit i % 64 of word i / 64
+pub type Bits = Vec<u64>;
 
-    let input_lines: Vec<&str> = input.lines().collect();
-    let mut pattern: Matrix<u8> = Matrix::new(1, input_lines[0].len(), 0);
-    let mut first_line_flag = true;
+fn set_bit(bits: &mut Bits, index: usize) {
+    bits[index / 64] |= 1 << (index % 64);
+}
 
-    for line in input_lines {
-        if line.len() == 0 {
-            first_line_flag = true;
-            valley.push(pattern.clone());
-            continue;
-        }
+fn count_differences_between(bits1: &Bits, bits2: &Bits) -> u32 {
+    bits1
+        .iter()
+        .zip(bits2)
+        .map(|(word1, word2)| (word1 ^ word2).count_ones())
+        .sum()
+}
 
-        if first_line_flag {
-            pattern = Matrix::new(1, line.len(), 0);
-        }
+// Each row/col packed as bits: bit i of a row is col i, bit i of a col is row i
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub rows: Vec<Bits>,
+    pub cols: Vec<Bits>,
+}
 
-        let mut values: Vec<u8> = Vec::new();
-        for character in line.chars() {
-            let value: u8 = match character {
-                '.' => 0,
-                '#' => 1,
-                _ => panic!("Unknown character: {}", character),
-            };
-            values.push(value);
+impl Pattern {
+    pub fn new(lines: &[&str]) -> Pattern {
+        let num_cols = lines[0].len();
+        let num_words = |num_bits: usize| num_bits.div_ceil(64);
+
+        let mut rows: Vec<Bits> = Vec::with_capacity(lines.len());
+        let mut cols: Vec<Bits> = vec![vec![0; num_words(lines.len())]; num_cols];
+        for (row, line) in lines.iter().enumerate() {
+            if line.len() != num_cols {
+                panic!("All the rows of a pattern must have the same length");
+            }
+            let mut row_bits: Bits = vec![0; num_words(num_cols)];
+            for (col, character) in line.chars().enumerate() {
+                match character {
+                    '.' => (),
+                    '#' => {
+                        set_bit(&mut row_bits, col);
+                        set_bit(&mut cols[col], row);
+                    }
+                    _ => panic!("Unknown character: {}", character),
+                };
+            }
+            rows.push(row_bits);
         }
+        Pattern { rows, cols }
+    }
+}
 
-        pattern.insert_row(pattern.rows, values);
+pub fn process_input(input: &str) -> Vec<Pattern> {
+    let mut valley: Vec<Pattern> = Vec::new();
+    let mut pattern_lines: Vec<&str> = Vec::new();
 
-        if first_line_flag {
-            pattern.remove_row(0);
-            first_line_flag = false;
+    for line in input.lines() {
+        if line.is_empty() {
+            if !pattern_lines.is_empty() {
+                valley.push(Pattern::new(&pattern_lines));
+                pattern_lines.clear();
+            }
+            continue;
         }
+        pattern_lines.push(line);
+    }
+    if !pattern_lines.is_empty() {
+        valley.push(Pattern::new(&pattern_lines));
     }
-    valley.push(pattern.clone());
     return valley;
 }
 
-fn find_simetry(pattern: &Matrix<u8>, row1: usize, row2: usize) -> bool {
-    if row1 >= pattern.rows || row2 >= pattern.rows || row1 >= row2 {
-        return false;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    // Line between two rows
+    Horizontal,
+    // Line between two cols
+    Vertical,
+}
+
+// (row, col) of a cell and of its mirrored cell
+pub type Smudge = ((usize, usize), (usize, usize));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reflection {
+    pub axis: Axis,
+    // Number of rows/cols before the line
+    pub position: usize,
+    // Mirrored cells that do not match, fixing either one makes a perfect mirror
+    pub smudges: Vec<Smudge>,
+}
+
+impl Reflection {
+    pub fn summary(&self) -> u64 {
+        match self.axis {
+            Axis::Horizontal => 100 * self.position as u64,
+            Axis::Vertical => self.position as u64,
+        }
+    }
+}
+
+fn get_reflection(lines: &[Bits], axis: Axis, position: usize) -> Reflection {
+    // Bits of a row are cols and bits of a col are rows
+    let coords = |line: usize, bit: usize| match axis {
+        Axis::Horizontal => (line, bit),
+        Axis::Vertical => (bit, line),
+    };
+
+    let mut smudges: Vec<Smudge> = Vec::new();
+    let num_pairs = position.min(lines.len() - position);
+    for offset in 0..num_pairs {
+        let line1 = position - 1 - offset;
+        let line2 = position + offset;
+        for (word, (word1, word2)) in lines[line1].iter().zip(&lines[line2]).enumerate() {
+            let mut differences = word1 ^ word2;
+            while differences != 0 {
+                let bit = word * 64 + differences.trailing_zeros() as usize;
+                smudges.push((coords(line1, bit), coords(line2, bit)));
+                differences &= differences - 1;
+            }
+        }
+    }
+    Reflection {
+        axis,
+        position,
+        smudges,
     }
+}
+
+// Number of cells that differ across each candidate line, the line at
+// position p is stored at index p - 1
+pub fn count_differences(lines: &[Bits]) -> Vec<u32> {
+    (1..lines.len())
+        .map(|position| {
+            let num_pairs = position.min(lines.len() - position);
+            (0..num_pairs)
+                .map(|offset| {
+                    count_differences_between(
+                        &lines[position - 1 - offset],
+                        &lines[position + offset],
+                    )
+                })
+                .sum()
+        })
+        .collect()
+}
 
-    let simetry = pattern.compare_rows(row1, row2);
-    if row1 == 0 || row2 == pattern.rows - 1 || !simetry {
-        return simetry;
+fn axes(pattern: &Pattern) -> [(&[Bits], Axis); 2] {
+    [
+        (&pattern.rows, Axis::Horizontal),
+        (&pattern.cols, Axis::Vertical),
+    ]
+}
+
+// Every candidate line, with all the cells that differ across it
+#[cfg_attr(not(test), allow(unused))]
+pub fn find_reflections(pattern: &Pattern) -> Vec<Reflection> {
+    let mut reflections: Vec<Reflection> = Vec::new();
+    for (lines, axis) in axes(pattern) {
+        for position in 1..lines.len() {
+            reflections.push(get_reflection(lines, axis, position));
+        }
     }
-    let next_row1 = row1 - 1;
-    let next_row2 = row2 + 1;
-    return find_simetry(pattern, next_row1, next_row2);
+    reflections
 }
 
-pub fn find_mirrors(pattern: &Matrix<u8>) -> Vec<u64> {
-    let mut mirrows: Vec<u64> = Vec::new();
-    for row1 in 1..pattern.rows {
-        if find_simetry(pattern, row1 - 1, row1) {
-            mirrows.push(row1 as u64);
+// Lines that become perfect mirrors after fixing exactly num_smudges cells
+pub fn find_mirrors(pattern: &Pattern, num_smudges: usize) -> Vec<Reflection> {
+    let mut mirrors: Vec<Reflection> = Vec::new();
+    for (lines, axis) in axes(pattern) {
+        for (index, differences) in count_differences(lines).into_iter().enumerate() {
+            if differences as usize == num_smudges {
+                mirrors.push(get_reflection(lines, axis, index + 1));
+            }
         }
     }
-    return mirrows;
+    mirrors
 }
 
-fn advent_of_code(input_text: &str) -> String {
+pub fn summarize(valley: &[Pattern], num_smudges: usize) -> u64 {
     let mut result = 0;
-    let valley: Vec<Matrix<u8>> = process_input(input_text);
-    for pattern in &valley {
-        let count_rows = find_mirrors(pattern);
-        let transposed_pattern = pattern.transpose();
-        let count_cols = find_mirrors(&transposed_pattern);
-        for row in count_rows {
-            result += 100 * row;
-        }
-        for col in count_cols {
-            result += col;
+    for pattern in valley {
+        for reflection in find_mirrors(pattern, num_smudges) {
+            result += reflection.summary();
         }
     }
+    result
+}
+
+fn advent_of_code(input_text: &str) -> String {
+    let valley: Vec<Pattern> = process_input(input_text);
+    let result = summarize(&valley, 0);
     return result.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(env!("CARGO_MANIFEST_DIR"), "part1", &[]);
+    let input: String = io.content();
 
     // Process data
     let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
 }
 
 #[cfg(test)]
@@ -113,4 +220,50 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_smudges() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let valley = process_input(&input);
+
+        let mirrors = find_mirrors(&valley[0], 1);
+        assert_eq!(mirrors.len(), 1);
+        assert_eq!(mirrors[0].axis, Axis::Horizontal);
+        assert_eq!(mirrors[0].position, 3);
+        assert_eq!(mirrors[0].smudges, vec![((0, 0), (5, 0))]);
+
+        let mirrors = find_mirrors(&valley[1], 1);
+        assert_eq!(mirrors.len(), 1);
+        assert_eq!(mirrors[0].position, 1);
+        assert_eq!(mirrors[0].smudges, vec![((0, 4), (1, 4))]);
+
+        let reflections = find_reflections(&valley[0]);
+        assert_eq!(
+            reflections.len(),
+            valley[0].rows.len() - 1 + valley[0].cols.len() - 1
+        );
+        let vertical_5 = reflections
+            .iter()
+            .find(|reflection| reflection.axis == Axis::Vertical && reflection.position == 5)
+            .unwrap();
+        assert!(vertical_5.smudges.is_empty());
+        assert_eq!(count_differences(&valley[0].cols)[4], 0);
+        assert_eq!(count_differences(&valley[0].rows)[2], 1);
+
+        // Rows longer than a 64 bit word
+        let row1: String = (0..70)
+            .map(|col| if col % 3 == 0 { '#' } else { '.' })
+            .collect();
+        let mut row2: Vec<u8> = row1.clone().into_bytes();
+        row2[66] = b'.';
+        let row2 = String::from_utf8(row2).unwrap();
+        let pattern = Pattern::new(&[&row1, &row2]);
+        let mirrors: Vec<Reflection> = find_mirrors(&pattern, 1)
+            .into_iter()
+            .filter(|mirror| mirror.axis == Axis::Horizontal)
+            .collect();
+        assert_eq!(mirrors.len(), 1);
+        assert_eq!(mirrors[0].smudges, vec![((0, 66), (1, 66))]);
+    }
 }
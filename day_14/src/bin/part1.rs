@@ -1,25 +1,65 @@
+use lib_utils::render::{write_svg, CellStyle, Color, RenderOptions, Shape};
 use lib_utils::Matrix;
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use std::fmt;
+use test_utils::{Flag, SolverIo};
 
-pub fn process_input(input: &str) -> Matrix<u8> {
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub enum Tile {
+    Empty,
+    Round,
+    Cube,
+}
+
+impl Tile {
+    pub fn from_char(character: char) -> Option<Tile> {
+        match character {
+            '.' => Some(Tile::Empty),
+            'O' => Some(Tile::Round),
+            '#' => Some(Tile::Cube),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Empty => '.',
+            Tile::Round => 'O',
+            Tile::Cube => '#',
+        }
+    }
+}
+
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+pub fn process_input(input: &str) -> Matrix<Tile> {
     let num_lines: usize = input.lines().count();
     let len_line: usize = input.lines().next().unwrap().len();
-    let mut platform: Matrix<u8> = Matrix::new(num_lines, len_line, 0);
+    let mut platform: Matrix<Tile> = Matrix::new(num_lines, len_line, Tile::Empty);
 
     for (row_idx, line) in input.lines().enumerate() {
         for (col_idx, character) in line.chars().enumerate() {
-            let value = match character {
-                '.' => 0,
-                'O' => 1,
-                '#' => 2,
-                _ => panic!("Unknown character: {}", character),
+            let tile = match Tile::from_char(character) {
+                Some(tile) => tile,
+                None => panic!("Unknown character: {}", character),
             };
-            platform.set_value(value, row_idx, col_idx);
+            platform.set_value(tile, row_idx, col_idx);
         }
     }
     platform
 }
 
+pub fn platform_style(tile: &Tile) -> Option<CellStyle> {
+    match tile {
+        Tile::Round => Some(CellStyle::new(Color::rgb(120, 120, 120), Shape::Circle)),
+        Tile::Cube => Some(CellStyle::new(Color::rgb(60, 40, 30), Shape::Square)),
+        Tile::Empty => None,
+    }
+}
+
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
 pub enum Direction {
     North,
@@ -28,77 +68,52 @@ pub enum Direction {
     West,
 }
 
-fn single_move_platform(platform: &mut Matrix<u8>, direction: &Direction) {
-    for row_aux in 0..platform.rows {
-        for col_aux in 0..platform.cols {
-            let row = match direction {
-                Direction::North => platform.rows - row_aux - 1,
-                _ => row_aux,
-            };
-            let col = match direction {
-                Direction::East => platform.cols - col_aux - 1,
-                _ => col_aux,
-            };
-            let value = *platform.at(row, col);
-            if value != 1 {
-                continue;
-            }
-            let mut move_row: usize = row;
-            let mut move_col: usize = col;
-            match direction {
-                Direction::North => {
-                    if move_row == 0 {
-                        continue;
-                    }
-                    move_row -= 1;
-                }
-                Direction::South => {
-                    if move_row == platform.rows - 1 {
-                        continue;
-                    }
-                    move_row += 1;
-                }
-                Direction::East => {
-                    if move_col == platform.cols - 1 {
-                        continue;
-                    }
-                    move_col += 1;
-                }
-                Direction::West => {
-                    if move_col == 0 {
-                        continue;
+pub fn move_platform(platform: &mut Matrix<Tile>, direction: &Direction) {
+    // Lines are scanned starting from the side the rocks roll to
+    let (num_lines, line_len) = match direction {
+        Direction::North | Direction::South => (platform.cols, platform.rows),
+        Direction::East | Direction::West => (platform.rows, platform.cols),
+    };
+    let coords = |line: usize, step: usize| match direction {
+        Direction::North => (step, line),
+        Direction::South => (line_len - step - 1, line),
+        Direction::West => (line, step),
+        Direction::East => (line, line_len - step - 1),
+    };
+
+    for line in 0..num_lines {
+        // Next step where a round rock can rest
+        let mut free_step: usize = 0;
+        for step in 0..line_len {
+            let (row, col) = coords(line, step);
+            match *platform.at(row, col) {
+                Tile::Round => {
+                    if step != free_step {
+                        let (free_row, free_col) = coords(line, free_step);
+                        platform.set_value(Tile::Round, free_row, free_col);
+                        platform.set_value(Tile::Empty, row, col);
                     }
-                    move_col -= 1;
+                    free_step += 1;
                 }
-            }
-            let next_cell_value = *platform.at(move_row, move_col);
-            if next_cell_value == 0 {
-                platform.set_value(value, move_row, move_col);
-                platform.set_value(0, row, col);
+                Tile::Cube => free_step = step + 1,
+                Tile::Empty => (),
             }
         }
     }
 }
 
-pub fn move_platform(platform: &mut Matrix<u8>, direction: &Direction) {
-    let num_iterations = match direction {
-        Direction::North => platform.rows,
-        Direction::South => platform.rows,
-        Direction::East => platform.cols,
-        Direction::West => platform.cols,
-    };
-    for _ in 0..num_iterations {
-        single_move_platform(platform, direction);
-    }
+// Prints the platform in puzzle notation after a tilt, for "--trace"
+pub fn trace_tilt(platform: &Matrix<Tile>, direction: &Direction) {
+    eprint!("Tilted {:?}:{}", direction, platform);
 }
 
-pub fn compute_load(platform: &Matrix<u8>) -> u64 {
+pub fn compute_load(platform: &Matrix<Tile>) -> u64 {
     let mut count_ = 0;
 
     for row in 0..platform.rows {
         for col in 0..platform.cols {
             let value = *platform.at(row, col);
-            if value == 1 {
+            if value == Tile::Round {
                 count_ += (platform.rows - row) as u64;
             }
         }
@@ -106,30 +121,52 @@ pub fn compute_load(platform: &Matrix<u8>) -> u64 {
     count_
 }
 
-fn advent_of_code(input_text: &str) -> String {
-    let mut platform: Matrix<u8> = process_input(input_text);
-    let direction = Direction::North;
-    move_platform(&mut platform, &direction);
+// Platform of the input tilted north and its load, shared by the answer,
+// the trace and the rendering
+pub fn tilt_north(input_text: &str) -> (Matrix<Tile>, u64) {
+    let mut platform: Matrix<Tile> = process_input(input_text);
+    move_platform(&mut platform, &Direction::North);
     let load = compute_load(&platform);
+    (platform, load)
+}
+
+#[cfg_attr(not(test), allow(unused))]
+fn advent_of_code(input_text: &str) -> String {
+    let (_, load) = tilt_north(input_text);
     return load.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part1/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(
+        env!("CARGO_MANIFEST_DIR"),
+        "part1",
+        &[Flag::switch("--trace"), Flag::value("--svg")],
+    );
+    let input: String = io.content();
+
+    // Process data, printing the tilted platform with "--trace"
+    let (platform, load) = tilt_north(&input);
+    if io.has_flag("--trace") {
+        trace_tilt(&platform, &Direction::North);
     }
+    let output: String = load.to_string();
 
-    // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    // Print result and write it to file
+    io.report(&output);
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part1/output.txt");
-    write_file_content(&output_path, &output);
+    // Render the tilted platform to the "--svg <path>" image
+    if let Some(image_path) = io.flag_value("--svg") {
+        if let Err(error) = write_svg(
+            image_path,
+            &platform,
+            &RenderOptions::default(),
+            platform_style,
+        ) {
+            eprintln!("Unable to write image {}: {}", image_path, error);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -137,6 +174,71 @@ mod tests {
     use super::*;
     use test_utils::{get_full_path, test_advent_of_code};
 
+    // Previous implementation, moving every rock one cell per pass
+    fn single_move_platform(platform: &mut Matrix<Tile>, direction: &Direction) {
+        for row_aux in 0..platform.rows {
+            for col_aux in 0..platform.cols {
+                let row = match direction {
+                    Direction::North => platform.rows - row_aux - 1,
+                    _ => row_aux,
+                };
+                let col = match direction {
+                    Direction::East => platform.cols - col_aux - 1,
+                    _ => col_aux,
+                };
+                let value = *platform.at(row, col);
+                if value != Tile::Round {
+                    continue;
+                }
+                let mut move_row: usize = row;
+                let mut move_col: usize = col;
+                match direction {
+                    Direction::North => {
+                        if move_row == 0 {
+                            continue;
+                        }
+                        move_row -= 1;
+                    }
+                    Direction::South => {
+                        if move_row == platform.rows - 1 {
+                            continue;
+                        }
+                        move_row += 1;
+                    }
+                    Direction::East => {
+                        if move_col == platform.cols - 1 {
+                            continue;
+                        }
+                        move_col += 1;
+                    }
+                    Direction::West => {
+                        if move_col == 0 {
+                            continue;
+                        }
+                        move_col -= 1;
+                    }
+                }
+                let next_cell_value = *platform.at(move_row, move_col);
+                if next_cell_value == Tile::Empty {
+                    platform.set_value(value, move_row, move_col);
+                    platform.set_value(Tile::Empty, row, col);
+                }
+            }
+        }
+    }
+
+    fn move_platform_step_by_step(platform: &mut Matrix<Tile>, direction: &Direction) {
+        let num_iterations = match direction {
+            Direction::North => platform.rows,
+            Direction::South => platform.rows,
+            Direction::East => platform.cols,
+            Direction::West => platform.cols,
+        };
+        for _ in 0..num_iterations {
+            single_move_platform(platform, direction);
+        }
+    }
+
     #[test]
     fn check_code() {
         let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
@@ -146,4 +248,52 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_against_step_by_step() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let mut platform: Matrix<Tile> = process_input(&input);
+        let mut expected: Matrix<Tile> = platform.clone();
+        let directions = [
+            Direction::North,
+            Direction::West,
+            Direction::South,
+            Direction::East,
+        ];
+        for _ in 0..3 {
+            for direction in directions.iter() {
+                move_platform(&mut platform, direction);
+                move_platform_step_by_step(&mut expected, direction);
+                assert_eq!(platform.data, expected.data);
+            }
+        }
+    }
+
+    #[test]
+    fn check_puzzle_notation() {
+        let input_test_path: &str = &get_full_path("src/bin/part1/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let mut platform: Matrix<Tile> = process_input(&input);
+        assert_eq!(format!("{platform}"), format!("\n{}\n", input.trim_end()));
+
+        move_platform(&mut platform, &Direction::North);
+        let expected = "
+OOOO.#.O..
+OO..#....#
+OO..O##..O
+O..#.OO...
+........#.
+..#....#.#
+..O..#.O.O
+..O.......
+#....###..
+#....#....
+";
+        assert_eq!(format!("{platform}"), expected);
+
+        assert_eq!(platform_style(&Tile::Empty), None);
+        assert!(platform_style(&Tile::Round).is_some());
+        assert!(platform_style(&Tile::Cube).is_some());
+    }
 }
@@ -1,63 +1,117 @@
-use std::collections::HashMap;
+use std::fs;
 
+use lib_utils::cycle::{find_cycle, Cycle};
 use lib_utils::Matrix;
-use test_utils::{get_file_content, get_full_path, write_file_content};
+use test_utils::{Flag, SolverIo};
 mod part1;
-use part1::{compute_load, move_platform, process_input, Direction};
-
-fn process_cycle(platform: &mut Matrix<u8>) {
-    let mut direction = Direction::North;
-    move_platform(platform, &direction);
-    direction = Direction::West;
-    move_platform(platform, &direction);
-    direction = Direction::South;
-    move_platform(platform, &direction);
-    direction = Direction::East;
-    move_platform(platform, &direction);
+use part1::{compute_load, move_platform, process_input, trace_tilt, Direction, Tile};
+
+// Tilts the platform in every direction, printing it after each tilt with trace
+fn process_cycle(platform: &mut Matrix<Tile>, trace: bool) {
+    for direction in [
+        Direction::North,
+        Direction::West,
+        Direction::South,
+        Direction::East,
+    ] {
+        move_platform(platform, &direction);
+        if trace {
+            trace_tilt(platform, &direction);
+        }
+    }
+}
+
+fn spin(platform: &Matrix<Tile>) -> Matrix<Tile> {
+    let mut next = platform.clone();
+    process_cycle(&mut next, false);
+    next
+}
+
+// Load after each spin cycle, until the platform starts repeating
+struct LoadHistory {
+    cycle: Cycle,
+    // loads[i] is the load after i spin cycles, for i in 0..(tail + period)
+    loads: Vec<u64>,
 }
 
-fn cycles_move(platform: &mut Matrix<u8>, cycles: u64) {
-    let mut cache: HashMap<Vec<u8>, u64> = HashMap::new();
-    let mut cycle = 0;
-
-    while cycle < cycles {
-        let key = platform.data.clone();
-        if cache.contains_key(&key) {
-            let cycle_len = cycle - cache.get(&key).unwrap();
-            let remaining_cycles = cycles - cycle;
-            let cycles_to_skip = remaining_cycles / cycle_len;
-            cycle += cycles_to_skip * cycle_len;
-        } else {
-            cache.insert(key, cycle);
+impl LoadHistory {
+    // With trace every tilt of the recorded spin cycles is printed
+    fn new(platform: &Matrix<Tile>, trace: bool) -> LoadHistory {
+        let cycle = find_cycle(platform, spin);
+        let num_states = (cycle.tail + cycle.period) as usize;
+        let mut loads: Vec<u64> = Vec::with_capacity(num_states);
+        let mut current = platform.clone();
+        for spin_cycle in 0..num_states {
+            loads.push(compute_load(&current));
+            if trace {
+                eprintln!("Spin cycle {}", spin_cycle + 1);
+            }
+            process_cycle(&mut current, trace);
         }
-        process_cycle(platform);
-        cycle += 1;
+        LoadHistory { cycle, loads }
+    }
+
+    fn load_after(&self, cycles: u64) -> u64 {
+        self.loads[self.cycle.reduce(cycles) as usize]
+    }
+
+    // One row per spin cycle, the phase is the position inside the period.
+    // Every row repeats the pre-period and period lengths
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("cycle,load,section,phase,pre_period,period\n");
+        let lengths = format!("{},{}", self.cycle.tail, self.cycle.period);
+        for (cycle, load) in self.loads.iter().enumerate() {
+            let cycle = cycle as u64;
+            if cycle < self.cycle.tail {
+                csv.push_str(&format!("{},{},pre-period,,{}\n", cycle, load, lengths));
+            } else {
+                let phase = cycle - self.cycle.tail;
+                csv.push_str(&format!(
+                    "{},{},period,{},{}\n",
+                    cycle, load, phase, lengths
+                ));
+            }
+        }
+        csv
     }
 }
 
+#[cfg_attr(not(test), allow(unused))]
 fn advent_of_code(input_text: &str) -> String {
-    let mut platform: Matrix<u8> = process_input(input_text);
-    cycles_move(&mut platform, 1000000000);
-    let load = compute_load(&platform);
+    let platform: Matrix<Tile> = process_input(input_text);
+    let history = LoadHistory::new(&platform, false);
+    let load = history.load_after(1000000000);
     return load.to_string();
 }
 
 #[cfg_attr(not(test), allow(unused))]
 fn main() {
-    // Get input from txt file content
-    let input_path: &str = &get_full_path("src/bin/part2/input.txt");
-    let input: String = get_file_content(&input_path);
-    if input.is_empty() {
-        panic!("Input is empty!");
-    }
+    // Get input from a path argument, stdin or the txt file
+    let io = SolverIo::from_env(
+        env!("CARGO_MANIFEST_DIR"),
+        "part2",
+        &[Flag::switch("--trace"), Flag::value("--csv")],
+    );
+    let input: String = io.content();
 
-    // Process data
-    let output: String = advent_of_code(&input);
-    println!("Result: {}", output);
+    // Process data, printing every tilt with "--trace"
+    let platform: Matrix<Tile> = process_input(&input);
+    let history = LoadHistory::new(&platform, io.has_flag("--trace"));
+    let output: String = history.load_after(1000000000).to_string();
 
-    // Write result to file
-    let output_path: &str = &get_full_path("src/bin/part2/output.txt");
-    write_file_content(&output_path, &output);
+    // Print result and write it to file
+    io.report(&output);
+    eprintln!(
+        "Pre-period: {}, period: {}",
+        history.cycle.tail, history.cycle.period
+    );
+
+    // Write the load history to plot it, to the "--csv <path>" file
+    if let Some(history_path) = io.flag_value("--csv") {
+        if let Err(error) = fs::write(history_path, history.to_csv()) {
+            eprintln!("Unable to write {}: {}", history_path, error);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -74,4 +128,34 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn check_load_history() {
+        let input_test_path: &str = &get_full_path("src/bin/part2/input_test.txt");
+        let input: String = std::fs::read_to_string(input_test_path).unwrap();
+        let platform: Matrix<Tile> = process_input(&input);
+        let history = LoadHistory::new(&platform, false);
+
+        // The example repeats every 7 cycles after the 3rd one
+        assert_eq!(history.cycle, Cycle { tail: 3, period: 7 });
+        assert_eq!(history.loads.len(), 10);
+        assert_eq!(history.load_after(1000000000), 64);
+
+        let csv = history.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "cycle,load,section,phase,pre_period,period");
+        assert_eq!(
+            lines[1],
+            format!("0,{},pre-period,,3,7", compute_load(&platform))
+        );
+        assert!(lines[4].ends_with(",period,0,3,7"));
+        assert_eq!(lines.len(), 11);
+
+        // Every row matches the load of actually spinning the platform
+        let mut current = platform.clone();
+        for cycle in 0..20 {
+            assert_eq!(history.load_after(cycle), compute_load(&current));
+            current = spin(&current);
+        }
+    }
 }
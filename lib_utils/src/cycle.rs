@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Sequence start, step(start), step(step(start)), ... that enters a loop after `tail` steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub tail: u64,
+    pub period: u64,
+}
+
+impl Cycle {
+    // Smallest number of steps that reaches the same state as n steps
+    pub fn reduce(&self, n: u64) -> u64 {
+        if n < self.tail {
+            n
+        } else {
+            self.tail + (n - self.tail) % self.period
+        }
+    }
+
+    pub fn state_after<S: Clone>(&self, start: &S, mut step: impl FnMut(&S) -> S, n: u64) -> S {
+        let mut state = start.clone();
+        for _ in 0..self.reduce(n) {
+            state = step(&state);
+        }
+        state
+    }
+}
+
+// Brent's algorithm, constant memory
+pub fn find_cycle<S: Clone + PartialEq>(start: &S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    // Find the period looking for the hare in windows of growing powers of two
+    let mut power: u64 = 1;
+    let mut period: u64 = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(start);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    // Both meet at the start of the loop when the hare is a period ahead
+    let mut tail: u64 = 0;
+    let mut tortoise = start.clone();
+    let mut hare = start.clone();
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        tail += 1;
+    }
+    Cycle { tail, period }
+}
+
+// Floyd's tortoise and hare, constant memory
+pub fn find_cycle_floyd<S: Clone + PartialEq>(start: &S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    let mut tortoise = step(start);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        let next = step(&hare);
+        hare = step(&next);
+    }
+
+    let mut tail: u64 = 0;
+    tortoise = start.clone();
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        tail += 1;
+    }
+
+    let mut period: u64 = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        period += 1;
+    }
+    Cycle { tail, period }
+}
+
+// Remember every visited state, each one is computed only once
+pub fn find_cycle_hash<S: Clone + Eq + Hash>(start: &S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    let mut seen: HashMap<S, u64> = HashMap::new();
+    let mut state = start.clone();
+    let mut index: u64 = 0;
+    loop {
+        if let Some(first_index) = seen.get(&state) {
+            return Cycle {
+                tail: *first_index,
+                period: index - first_index,
+            };
+        }
+        let next = step(&state);
+        seen.insert(state, index);
+        state = next;
+        index += 1;
+    }
+}
+
+// State reached after n steps, without running them all once the loop is found
+pub fn state_after<S: Clone + PartialEq>(start: &S, mut step: impl FnMut(&S) -> S, n: u64) -> S {
+    let cycle = find_cycle(start, &mut step);
+    cycle.state_after(start, step, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(value: &u64) -> u64 {
+        (value * value + 1) % 255
+    }
+
+    fn brute_force(start: u64) -> Cycle {
+        let mut sequence: Vec<u64> = vec![start];
+        loop {
+            let next = step(sequence.last().unwrap());
+            if let Some(tail) = sequence.iter().position(|&value| value == next) {
+                return Cycle {
+                    tail: tail as u64,
+                    period: (sequence.len() - tail) as u64,
+                };
+            }
+            sequence.push(next);
+        }
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        for start in 0..255 {
+            let expected = brute_force(start);
+            assert_eq!(find_cycle(&start, step), expected);
+            assert_eq!(find_cycle_floyd(&start, step), expected);
+            assert_eq!(find_cycle_hash(&start, step), expected);
+        }
+    }
+
+    #[test]
+    fn test_state_after() {
+        let mut expected: u64 = 3;
+        for n in 0..1000 {
+            assert_eq!(state_after(&3, step, n), expected);
+            expected = step(&expected);
+        }
+
+        let cycle = find_cycle(&3, step);
+        let n: u64 = 1000000000;
+        let equivalent = 1000 + (n - 1000) % cycle.period;
+        assert_eq!(
+            cycle.state_after(&3, step, n),
+            cycle.state_after(&3, step, equivalent)
+        );
+    }
+}
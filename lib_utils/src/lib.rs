@@ -1,6 +1,10 @@
+pub mod cycle;
+pub mod metric;
+pub mod render;
+
 use std::fmt;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Matrix<T> {
     pub data: Vec<T>,
     pub rows: usize,
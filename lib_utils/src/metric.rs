@@ -0,0 +1,227 @@
+use crate::Matrix;
+use std::collections::VecDeque;
+
+// (row, col)
+pub type Point = (u64, u64);
+
+pub trait Metric {
+    // None when there is no way to go from one point to the other
+    fn distance(&self, from: Point, to: Point) -> Option<u64>;
+
+    fn distances_from(&self, from: Point, targets: &[Point]) -> Vec<Option<u64>> {
+        targets.iter().map(|&to| self.distance(from, to)).collect()
+    }
+
+    // Sum of the distances between every pair of points, None if it does not fit in u64
+    fn pairwise_sum(&self, points: &[Point]) -> Option<u64> {
+        let mut distance_sum: u64 = 0;
+        for (index, &from) in points.iter().enumerate() {
+            for distance in self.distances_from(from, &points[index + 1..]) {
+                distance_sum = distance_sum.checked_add(distance?)?;
+            }
+        }
+        Some(distance_sum)
+    }
+
+    // False when only the points of a fixed grid can be measured, so they can not be moved
+    // apart, as when expanding a universe
+    fn accepts_any_point(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Manhattan;
+
+impl Metric for Manhattan {
+    fn distance(&self, from: Point, to: Point) -> Option<u64> {
+        from.0.abs_diff(to.0).checked_add(from.1.abs_diff(to.1))
+    }
+
+    // Rows and cols add up independently, each one over its sorted values
+    fn pairwise_sum(&self, points: &[Point]) -> Option<u64> {
+        let rows: Vec<u64> = points.iter().map(|&(row, _)| row).collect();
+        let cols: Vec<u64> = points.iter().map(|&(_, col)| col).collect();
+        sum_of_pairwise_differences(rows)?.checked_add(sum_of_pairwise_differences(cols)?)
+    }
+}
+
+// Accumulated in u128, where no partial sum of u64 values can overflow
+fn sum_of_pairwise_differences(mut values: Vec<u64>) -> Option<u64> {
+    values.sort_unstable();
+    let mut prefix_sum: u128 = 0;
+    let mut sum: u128 = 0;
+    for (index, &value) in values.iter().enumerate() {
+        sum += value as u128 * index as u128 - prefix_sum;
+        prefix_sum += value as u128;
+    }
+    u64::try_from(sum).ok()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chebyshev;
+
+impl Metric for Chebyshev {
+    fn distance(&self, from: Point, to: Point) -> Option<u64> {
+        Some(from.0.abs_diff(to.0).max(from.1.abs_diff(to.1)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EuclideanSquared;
+
+impl Metric for EuclideanSquared {
+    fn distance(&self, from: Point, to: Point) -> Option<u64> {
+        let row_distance = from.0.abs_diff(to.0);
+        let col_distance = from.1.abs_diff(to.1);
+        row_distance
+            .checked_mul(row_distance)?
+            .checked_add(col_distance.checked_mul(col_distance)?)
+    }
+}
+
+// Shortest path length moving N, S, W, E between cells that are not obstacles
+pub struct GridPath<'a, T> {
+    pub grid: &'a Matrix<T>,
+    pub is_obstacle: Box<dyn Fn(&T) -> bool + 'a>,
+}
+
+impl<'a, T: Clone> GridPath<'a, T> {
+    pub fn new(grid: &'a Matrix<T>, is_obstacle: impl Fn(&T) -> bool + 'a) -> GridPath<'a, T> {
+        GridPath {
+            grid,
+            is_obstacle: Box::new(is_obstacle),
+        }
+    }
+
+    fn is_free(&self, point: Point) -> bool {
+        let (row, col) = point;
+        row < self.grid.rows as u64
+            && col < self.grid.cols as u64
+            && !(self.is_obstacle)(self.grid.at(row as usize, col as usize))
+    }
+
+    // Breadth first search from a point to every reachable cell
+    pub fn distances_matrix(&self, from: Point) -> Matrix<Option<u64>> {
+        let mut distances: Matrix<Option<u64>> = Matrix::new(self.grid.rows, self.grid.cols, None);
+        if !self.is_free(from) {
+            return distances;
+        }
+        let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+        distances.set_value(Some(0), from.0 as usize, from.1 as usize);
+        queue.push_back((from.0 as usize, from.1 as usize));
+
+        while let Some((row, col)) = queue.pop_front() {
+            let distance = distances.at(row, col).unwrap();
+            let mut neighbours: Vec<(usize, usize)> = Vec::with_capacity(4);
+            if row > 0 {
+                neighbours.push((row - 1, col));
+            }
+            if row + 1 < self.grid.rows {
+                neighbours.push((row + 1, col));
+            }
+            if col > 0 {
+                neighbours.push((row, col - 1));
+            }
+            if col + 1 < self.grid.cols {
+                neighbours.push((row, col + 1));
+            }
+            for (next_row, next_col) in neighbours {
+                if distances.at(next_row, next_col).is_none()
+                    && self.is_free((next_row as u64, next_col as u64))
+                {
+                    distances.set_value(Some(distance + 1), next_row, next_col);
+                    queue.push_back((next_row, next_col));
+                }
+            }
+        }
+        distances
+    }
+}
+
+impl<T: Clone> Metric for GridPath<'_, T> {
+    fn distance(&self, from: Point, to: Point) -> Option<u64> {
+        self.distances_from(from, &[to])[0]
+    }
+
+    fn accepts_any_point(&self) -> bool {
+        false
+    }
+
+    // A single search answers every target
+    fn distances_from(&self, from: Point, targets: &[Point]) -> Vec<Option<u64>> {
+        let distances = self.distances_matrix(from);
+        targets
+            .iter()
+            .map(|&(row, col)| {
+                if row < self.grid.rows as u64 && col < self.grid.cols as u64 {
+                    *distances.at(row as usize, col as usize)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_metrics() {
+        let from: Point = (1, 5);
+        let to: Point = (4, 1);
+        assert_eq!(Manhattan.distance(from, to), Some(7));
+        assert_eq!(Chebyshev.distance(from, to), Some(4));
+        assert_eq!(EuclideanSquared.distance(from, to), Some(25));
+    }
+
+    #[test]
+    fn test_pairwise_sum() {
+        let points: Vec<Point> = vec![(0, 4), (2, 0), (7, 3), (7, 3), (1, 9)];
+        let expected: u64 = (0..points.len())
+            .flat_map(|i| ((i + 1)..points.len()).map(move |j| (i, j)))
+            .map(|(i, j)| Manhattan.distance(points[i], points[j]).unwrap())
+            .sum();
+        assert_eq!(Manhattan.pairwise_sum(&points), Some(expected));
+        assert_eq!(Chebyshev.pairwise_sum(&points[..2]), Some(4));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let far: Point = (u64::MAX, u64::MAX);
+        assert_eq!(Manhattan.distance((0, 0), far), None);
+        assert_eq!(Chebyshev.distance((0, 0), far), Some(u64::MAX));
+        assert_eq!(EuclideanSquared.distance((0, 0), (1 << 32, 0)), None);
+        assert_eq!(
+            EuclideanSquared.distance((0, 0), (u32::MAX as u64, 0)),
+            Some((u32::MAX as u64).pow(2))
+        );
+
+        // Each difference fits, their sum does not
+        let points: Vec<Point> = vec![(0, 0), (u64::MAX / 2, 0), (u64::MAX, 0)];
+        assert_eq!(Manhattan.pairwise_sum(&points), None);
+        assert_eq!(Manhattan.pairwise_sum(&points[1..]), Some(u64::MAX / 2 + 1));
+        assert_eq!(Chebyshev.pairwise_sum(&points), None);
+    }
+
+    #[test]
+    fn test_grid_path() {
+        // .#.
+        // .#.
+        // ...
+        let mut grid = Matrix::new(3, 3, '.');
+        grid.set_value('#', 0, 1);
+        grid.set_value('#', 1, 1);
+        let metric = GridPath::new(&grid, |cell: &char| *cell == '#');
+        assert!(!metric.accepts_any_point());
+
+        assert_eq!(metric.distance((0, 0), (0, 2)), Some(6));
+        assert_eq!(metric.distance((0, 0), (0, 1)), None);
+        assert_eq!(
+            metric.distances_from((2, 1), &[(0, 0), (2, 2), (5, 5)]),
+            vec![Some(3), Some(1), None]
+        );
+    }
+}
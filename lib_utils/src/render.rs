@@ -0,0 +1,470 @@
+use crate::Matrix;
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    Square,
+    Circle,
+    // Segments from the cell center to the selected borders (N, S, W, E)
+    Pipe {
+        north: bool,
+        south: bool,
+        west: bool,
+        east: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellStyle {
+    pub color: Color,
+    pub shape: Shape,
+}
+
+impl CellStyle {
+    pub fn new(color: Color, shape: Shape) -> CellStyle {
+        CellStyle { color, shape }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    pub cell_size: usize,
+    pub background: Color,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            cell_size: 10,
+            background: Color::rgb(255, 255, 255),
+        }
+    }
+}
+
+pub fn render_svg<T: Clone>(
+    matrix: &Matrix<T>,
+    options: &RenderOptions,
+    style: impl Fn(&T) -> Option<CellStyle>,
+) -> String {
+    let size = options.cell_size;
+    let width = matrix.cols * size;
+    let height = matrix.rows * size;
+    let half = size as f64 / 2.0;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+    svg.push_str(&format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+        width,
+        height,
+        options.background.to_hex()
+    ));
+
+    for row in 0..matrix.rows {
+        for col in 0..matrix.cols {
+            let cell_style = match style(matrix.at(row, col)) {
+                Some(cell_style) => cell_style,
+                None => continue,
+            };
+            let x = col * size;
+            let y = row * size;
+            let fill = cell_style.color.to_hex();
+            match cell_style.shape {
+                Shape::Square => svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    x, y, size, size, fill
+                )),
+                Shape::Circle => svg.push_str(&format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+                    x as f64 + half,
+                    y as f64 + half,
+                    circle_radius(size),
+                    fill
+                )),
+                Shape::Pipe {
+                    north,
+                    south,
+                    west,
+                    east,
+                } => {
+                    let cx = x as f64 + half;
+                    let cy = y as f64 + half;
+                    let ends = [
+                        (north, cx, y as f64),
+                        (south, cx, (y + size) as f64),
+                        (west, x as f64, cy),
+                        (east, (x + size) as f64, cy),
+                    ];
+                    for (enabled, ex, ey) in ends {
+                        if enabled {
+                            svg.push_str(&format!(
+                                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"square\"/>\n",
+                                cx, cy, ex, ey, fill, pipe_width(size)
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn render_png<T: Clone>(
+    matrix: &Matrix<T>,
+    options: &RenderOptions,
+    style: impl Fn(&T) -> Option<CellStyle>,
+) -> Vec<u8> {
+    let size = options.cell_size;
+    let width = matrix.cols * size;
+    let height = matrix.rows * size;
+
+    // Each scanline starts with the filter type byte (0 = None)
+    let mut raw: Vec<u8> = Vec::with_capacity(height * (width * 3 + 1));
+    for row in 0..matrix.rows {
+        let styles: Vec<Option<CellStyle>> = (0..matrix.cols)
+            .map(|col| style(matrix.at(row, col)))
+            .collect();
+        for y in 0..size {
+            raw.push(0);
+            for cell_style in styles.iter() {
+                for x in 0..size {
+                    let color = match cell_style {
+                        Some(cell_style) if is_inside_shape(&cell_style.shape, size, x, y) => {
+                            cell_style.color
+                        }
+                        _ => options.background,
+                    };
+                    raw.extend_from_slice(&[color.r, color.g, color.b]);
+                }
+            }
+        }
+    }
+
+    let mut header: Vec<u8> = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // Bit depth 8, color type 2 (RGB), default compression, filter and no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    write_png_chunk(&mut png, b"IHDR", &header);
+    write_png_chunk(&mut png, b"IDAT", &zlib_store(&raw));
+    write_png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+pub fn write_svg<T: Clone>(
+    file_name: &str,
+    matrix: &Matrix<T>,
+    options: &RenderOptions,
+    style: impl Fn(&T) -> Option<CellStyle>,
+) -> std::io::Result<()> {
+    fs::write(file_name, render_svg(matrix, options, style))
+}
+
+pub fn write_png<T: Clone>(
+    file_name: &str,
+    matrix: &Matrix<T>,
+    options: &RenderOptions,
+    style: impl Fn(&T) -> Option<CellStyle>,
+) -> std::io::Result<()> {
+    fs::write(file_name, render_png(matrix, options, style))
+}
+
+fn circle_radius(size: usize) -> f64 {
+    size as f64 * 0.4
+}
+
+fn pipe_width(size: usize) -> usize {
+    (size / 3).max(1)
+}
+
+fn is_inside_shape(shape: &Shape, size: usize, x: usize, y: usize) -> bool {
+    // Distances from the pixel center to the cell center
+    let half = size as f64 / 2.0;
+    let dx = x as f64 + 0.5 - half;
+    let dy = y as f64 + 0.5 - half;
+    match shape {
+        Shape::Square => true,
+        Shape::Circle => {
+            let radius = circle_radius(size);
+            dx * dx + dy * dy <= radius * radius
+        }
+        Shape::Pipe {
+            north,
+            south,
+            west,
+            east,
+        } => {
+            let half_width = pipe_width(size) as f64 / 2.0;
+            let vertical = dx.abs() <= half_width
+                && ((*north && dy <= half_width) || (*south && dy >= -half_width));
+            let horizontal = dy.abs() <= half_width
+                && ((*west && dx <= half_width) || (*east && dx >= -half_width));
+            vertical || horizontal
+        }
+    }
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn zlib_store(data: &[u8]) -> Vec<u8> {
+    // Deflate stream made of uncompressed (stored) blocks
+    const MAX_BLOCK: usize = 65535;
+    let mut out: Vec<u8> = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(is_last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFF_FFFF;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for byte in data {
+        a = (a + *byte as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard() -> Matrix<u8> {
+        let mut matrix = Matrix::new(2, 3, 0);
+        matrix.set_value(1, 0, 0);
+        matrix.set_value(2, 1, 2);
+        matrix
+    }
+
+    fn style(value: &u8) -> Option<CellStyle> {
+        match value {
+            1 => Some(CellStyle::new(Color::rgb(255, 0, 0), Shape::Square)),
+            2 => Some(CellStyle::new(Color::rgb(0, 0, 255), Shape::Circle)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let options = RenderOptions::default();
+        let svg = render_svg(&checkerboard(), &options, style);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("width=\"30\" height=\"20\""));
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"10\" height=\"10\" fill=\"#ff0000\"/>"));
+        assert!(svg.contains("<circle cx=\"25\" cy=\"15\" r=\"4\" fill=\"#0000ff\"/>"));
+    }
+
+    // PNG chunks (type, data), checking each CRC
+    fn png_chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        let mut chunks = Vec::new();
+        let mut position = 8;
+        while position < png.len() {
+            let len = u32::from_be_bytes(png[position..position + 4].try_into().unwrap()) as usize;
+            let chunk = &png[position + 4..position + 8 + len];
+            let crc = u32::from_be_bytes(
+                png[position + 8 + len..position + 12 + len]
+                    .try_into()
+                    .unwrap(),
+            );
+            assert_eq!(crc32(chunk), crc);
+            chunks.push((chunk[0..4].try_into().unwrap(), chunk[4..].to_vec()));
+            position += 12 + len;
+        }
+        chunks
+    }
+
+    // Inflate a zlib stream made of stored blocks, checking the Adler-32
+    fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+        assert_eq!(u16::from_be_bytes([zlib[0], zlib[1]]) % 31, 0);
+        let mut data: Vec<u8> = Vec::new();
+        let mut position = 2;
+        loop {
+            let header = zlib[position];
+            assert_eq!((header >> 1) & 0b11, 0, "Only stored blocks are expected");
+            let len = u16::from_le_bytes([zlib[position + 1], zlib[position + 2]]);
+            let nlen = u16::from_le_bytes([zlib[position + 3], zlib[position + 4]]);
+            assert_eq!(len, !nlen);
+            position += 5;
+            data.extend_from_slice(&zlib[position..position + len as usize]);
+            position += len as usize;
+            if header & 1 == 1 {
+                break;
+            }
+        }
+        let adler = u32::from_be_bytes(zlib[position..position + 4].try_into().unwrap());
+        assert_eq!(adler32(&data), adler);
+        data
+    }
+
+    fn decode_png(png: &[u8]) -> (usize, usize, Vec<Vec<Color>>) {
+        assert_eq!(
+            &png[0..8],
+            &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]
+        );
+        let chunks = png_chunks(png);
+        let types: Vec<&[u8; 4]> = chunks.iter().map(|(chunk_type, _)| chunk_type).collect();
+        assert_eq!(types, vec![b"IHDR", b"IDAT", b"IEND"]);
+
+        let header = &chunks[0].1;
+        let width = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+        let height = u32::from_be_bytes(header[4..8].try_into().unwrap()) as usize;
+        assert_eq!(&header[8..], &[8, 2, 0, 0, 0]);
+
+        let raw = inflate_stored(&chunks[1].1);
+        assert_eq!(raw.len(), height * (width * 3 + 1));
+        let pixels = raw
+            .chunks(width * 3 + 1)
+            .map(|scanline| {
+                assert_eq!(scanline[0], 0);
+                scanline[1..]
+                    .chunks(3)
+                    .map(|rgb| Color::rgb(rgb[0], rgb[1], rgb[2]))
+                    .collect()
+            })
+            .collect();
+        (width, height, pixels)
+    }
+
+    #[test]
+    fn test_render_png() {
+        let background = Color::rgb(0, 0, 0);
+        let red = Color::rgb(255, 0, 0);
+        let blue = Color::rgb(0, 0, 255);
+        let options = RenderOptions {
+            cell_size: 4,
+            background,
+        };
+        let (width, height, pixels) = decode_png(&render_png(&checkerboard(), &options, style));
+        assert_eq!((width, height), (12, 8));
+
+        // Square fills its whole cell, circle of radius 1.6 leaves out the cell corners
+        assert_eq!(pixels.len(), height);
+        for (y, row) in pixels.iter().enumerate() {
+            assert_eq!(row.len(), width);
+            for (x, pixel) in row.iter().enumerate() {
+                let expected = if x < 4 && y < 4 {
+                    red
+                } else if (8..12).contains(&x)
+                    && (4..8).contains(&y)
+                    && !([8, 11].contains(&x) && [4, 7].contains(&y))
+                {
+                    blue
+                } else {
+                    background
+                };
+                assert_eq!(*pixel, expected, "Pixel ({}, {})", x, y);
+            }
+        }
+
+        // Empty matrix still holds a valid (empty) stream
+        let (width, height, pixels) =
+            decode_png(&render_png(&Matrix::new(0, 0, 0u8), &options, style));
+        assert_eq!((width, height, pixels.len()), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_render_pipes() {
+        // North-east elbow next to a west-east straight pipe
+        let pipe = |north, south, west, east| {
+            CellStyle::new(
+                Color::rgb(0, 255, 0),
+                Shape::Pipe {
+                    north,
+                    south,
+                    west,
+                    east,
+                },
+            )
+        };
+        let mut matrix = Matrix::new(1, 2, pipe(true, false, false, true));
+        matrix.set_value(pipe(false, false, true, true), 0, 1);
+        let options = RenderOptions {
+            cell_size: 6,
+            background: Color::rgb(0, 0, 0),
+        };
+
+        let svg = render_svg(&matrix, &options, |cell| Some(*cell));
+        assert_eq!(svg.matches("<line").count(), 4);
+        assert!(svg.contains(
+            "<line x1=\"3\" y1=\"3\" x2=\"3\" y2=\"0\" stroke=\"#00ff00\" stroke-width=\"2\""
+        ));
+        assert!(svg.contains("<line x1=\"9\" y1=\"3\" x2=\"6\" y2=\"3\""));
+
+        // Segments 2 pixels wide through the cell center
+        let (_, _, pixels) = decode_png(&render_png(&matrix, &options, |cell| Some(*cell)));
+        let drawn: Vec<String> = pixels
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|color| if color.g == 255 { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            drawn,
+            vec![
+                "..##........",
+                "..##........",
+                "..##########",
+                "..##########",
+                "............",
+                "............",
+            ]
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
 
 pub fn test_advent_of_code(
     input_test: &str,
@@ -53,6 +55,23 @@ pub fn get_file_content(file_name: &str) -> String {
     String::new() // Return an empty string in case of failure
 }
 
+// Lines of a buffered reader, read one at a time so the input is never fully in memory
+pub fn read_lines<R: BufRead>(reader: R) -> impl Iterator<Item = String> {
+    reader
+        .lines()
+        .map(|line| line.expect("Unable to read line."))
+}
+
+pub fn get_file_lines(file_name: &str) -> impl Iterator<Item = String> {
+    let file: File = File::open(file_name)
+        .unwrap_or_else(|error| panic!("Unable to open file {}: {}", file_name, error));
+    read_lines(BufReader::new(file))
+}
+
+pub fn get_stdin_lines() -> impl Iterator<Item = String> {
+    read_lines(io::stdin().lock())
+}
+
 pub fn write_file_content(file_name: &str, content: &str) -> bool {
     if content.is_empty() {
         panic!("Input is empty!");
@@ -71,10 +90,234 @@ pub fn write_file_content(file_name: &str, content: &str) -> bool {
     false // Return false in case of failure
 }
 
+pub enum InputSource {
+    File(String),
+    Stdin,
+}
+
+// Command line flag of a solver, "--name" alone or followed by a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flag {
+    pub name: &'static str,
+    pub takes_value: bool,
+    // The answer is no longer the one of the puzzle, so output.txt is left untouched
+    pub changes_answer: bool,
+}
+
+impl Flag {
+    pub const fn switch(name: &'static str) -> Flag {
+        Flag {
+            name,
+            takes_value: false,
+            changes_answer: false,
+        }
+    }
+
+    pub const fn value(name: &'static str) -> Flag {
+        Flag {
+            name,
+            takes_value: true,
+            changes_answer: false,
+        }
+    }
+
+    pub const fn changing_answer(self) -> Flag {
+        Flag {
+            changes_answer: true,
+            ..self
+        }
+    }
+}
+
+// Flag found on the command line, the value is empty for a switch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliOption {
+    pub flag: Flag,
+    pub value: String,
+}
+
+// Input and answer of a solver binary, read from its command line:
+//   partN [<path> | --input <path>] [--json] [day flags]
+// A "-" path reads stdin. Without a path the puzzle input of the crate is solved, from any
+// working directory. Day flags are declared by each binary, any other argument is an error
+pub struct SolverIo {
+    pub crate_dir: String,
+    pub part: String,
+    pub input: InputSource,
+    pub json: bool,
+    // Day flags in command line order
+    pub options: Vec<CliOption>,
+}
+
+impl SolverIo {
+    pub fn from_args(
+        args: &[String],
+        crate_dir: &str,
+        part: &str,
+        flags: &[Flag],
+    ) -> Result<SolverIo, String> {
+        let mut input: Option<String> = None;
+        let mut json = false;
+        let mut options: Vec<CliOption> = Vec::new();
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            let mut next_value = |name: &str| -> Result<String, String> {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("Missing value for {}", name))
+            };
+            match arg.as_str() {
+                "--input" => match input {
+                    Some(_) => return Err(String::from("Input given more than once")),
+                    None => input = Some(next_value(arg)?),
+                },
+                "--json" => json = true,
+                name if name.starts_with("--") => {
+                    let flag: Flag = match flags.iter().find(|flag| flag.name == name) {
+                        Some(flag) => *flag,
+                        None => return Err(format!("Unknown argument: {}", name)),
+                    };
+                    let value: String = match flag.takes_value {
+                        true => next_value(name)?,
+                        false => String::new(),
+                    };
+                    options.push(CliOption { flag, value });
+                }
+                path => match input {
+                    Some(_) => return Err(format!("Unexpected argument: {}", path)),
+                    None => input = Some(String::from(path)),
+                },
+            }
+        }
+
+        let input = match input {
+            Some(path) if path == "-" => InputSource::Stdin,
+            Some(path) => InputSource::File(path),
+            None => InputSource::File(format!("{}/src/bin/{}/input.txt", crate_dir, part)),
+        };
+        Ok(SolverIo {
+            crate_dir: String::from(crate_dir),
+            part: String::from(part),
+            input,
+            json,
+            options,
+        })
+    }
+
+    // Call with env!("CARGO_MANIFEST_DIR") so paths do not depend on the working directory.
+    // Exits on invalid arguments
+    pub fn from_env(crate_dir: &str, part: &str, flags: &[Flag]) -> SolverIo {
+        let args: Vec<String> = std::env::args().collect();
+        match SolverIo::from_args(&args, crate_dir, part, flags) {
+            Ok(io) => io,
+            Err(error) => {
+                let flags: Vec<String> = flags
+                    .iter()
+                    .map(|flag| match flag.takes_value {
+                        true => format!("[{} <value>]", flag.name),
+                        false => format!("[{}]", flag.name),
+                    })
+                    .collect();
+                eprintln!("{}", error);
+                eprintln!(
+                    "Usage: {} [<path> | --input <path>] [--json] {}",
+                    part,
+                    flags.join(" ")
+                );
+                std::process::exit(2);
+            }
+        }
+    }
+
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.options.iter().any(|option| option.flag.name == name)
+    }
+
+    // Value of the last occurrence of the flag
+    pub fn flag_value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|option| option.flag.name == name)
+            .map(|option| option.value.as_str())
+    }
+
+    pub fn is_default_input(&self) -> bool {
+        match &self.input {
+            InputSource::File(path) => *path == self.crate_file("input.txt"),
+            InputSource::Stdin => false,
+        }
+    }
+
+    // File next to the part sources
+    pub fn crate_file(&self, file_name: &str) -> String {
+        format!("{}/src/bin/{}/{}", self.crate_dir, self.part, file_name)
+    }
+
+    // Files of the crate are only updated with the answer of the puzzle, its own input without
+    // any flag changing the answer, and only when they exist so none is ever created
+    pub fn output_file(&self, file_name: &str) -> Option<String> {
+        let output_path: String = self.crate_file(file_name);
+        let is_puzzle_answer: bool = self.is_default_input()
+            && !self.options.iter().any(|option| option.flag.changes_answer);
+        match is_puzzle_answer && Path::new(&output_path).is_file() {
+            true => Some(output_path),
+            false => None,
+        }
+    }
+
+    pub fn lines(&self) -> Box<dyn Iterator<Item = String>> {
+        match &self.input {
+            InputSource::File(path) => Box::new(get_file_lines(path)),
+            InputSource::Stdin => Box::new(get_stdin_lines()),
+        }
+    }
+
+    pub fn content(&self) -> String {
+        let content: String = match &self.input {
+            InputSource::File(path) => fs::read_to_string(path)
+                .unwrap_or_else(|error| panic!("Unable to read file {}: {}", path, error)),
+            InputSource::Stdin => io::read_to_string(io::stdin()).expect("Unable to read stdin."),
+        };
+        if content.is_empty() {
+            panic!("Input is empty!");
+        }
+        content
+    }
+
+    pub fn to_json(&self, answer: &str) -> String {
+        let day = Path::new(&self.crate_dir)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        // Numbers are kept as numbers, anything else is quoted
+        let answer = match answer.parse::<i128>() {
+            Ok(_) => String::from(answer),
+            Err(_) => format!("\"{}\"", answer.replace('\\', "\\\\").replace('"', "\\\"")),
+        };
+        format!(
+            "{{\"day\": \"{}\", \"part\": \"{}\", \"answer\": {}}}",
+            day, self.part, answer
+        )
+    }
+
+    // Print the answer to stdout and keep it in output.txt
+    pub fn report(&self, answer: &str) {
+        match self.json {
+            true => println!("{}", self.to_json(answer)),
+            false => println!("{}", answer),
+        }
+        if let Some(output_path) = self.output_file("output.txt") {
+            if !write_file_content(&output_path, answer) {
+                eprintln!("Unable to write {}", output_path);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::Path;
 
     #[test]
     fn test() {
@@ -91,4 +334,92 @@ mod tests {
 
         assert!(result, "The files are not equal.");
     }
+
+    #[test]
+    fn test_lines() {
+        let input_test_path: &Path = Path::new("src/test/input_test_file.txt");
+        let content: String = fs::read_to_string(input_test_path).unwrap();
+        let lines: Vec<String> = get_file_lines(input_test_path.to_str().unwrap()).collect();
+        assert_eq!(lines, content.lines().collect::<Vec<&str>>());
+
+        let reader = io::Cursor::new("first\r\nsecond\n\nlast");
+        let lines: Vec<String> = read_lines(reader).collect();
+        assert_eq!(lines, vec!["first", "second", "", "last"]);
+    }
+
+    #[test]
+    fn test_solver_io() {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+        const FLAGS: &[Flag] = &[
+            Flag::value("--unfold").changing_answer(),
+            Flag::switch("--trace"),
+        ];
+
+        let io = SolverIo::from_args(&args(&["part1"]), "/aoc/day_01", "part1", &[]).unwrap();
+        assert!(io.is_default_input());
+        // Missing files are never created
+        assert_eq!(io.output_file("output.txt"), None);
+
+        let io = SolverIo::from_args(
+            &args(&["part2", "-", "--json"]),
+            "/aoc/day_01",
+            "part2",
+            &[],
+        )
+        .unwrap();
+        assert!(matches!(io.input, InputSource::Stdin));
+        assert_eq!(
+            io.to_json("42"),
+            "{\"day\": \"day_01\", \"part\": \"part2\", \"answer\": 42}"
+        );
+        assert!(io
+            .to_json("a \"b\"")
+            .ends_with("\"answer\": \"a \\\"b\\\"\"}"));
+
+        // The path is the first argument that is not a flag or its value
+        for command in [
+            vec!["part2", "--unfold", "1000", "in.txt"],
+            vec!["part2", "--trace", "--unfold", "1000", "--input", "in.txt"],
+        ] {
+            let io = SolverIo::from_args(&args(&command), "/aoc/day_12", "part2", FLAGS).unwrap();
+            assert!(matches!(io.input, InputSource::File(ref path) if path == "in.txt"));
+            assert_eq!(io.flag_value("--unfold"), Some("1000"));
+            assert!(!io.json);
+        }
+
+        let from_args = |command: &[&str]| {
+            SolverIo::from_args(&args(command), "/aoc/day_12", "part2", FLAGS).err()
+        };
+        assert_eq!(
+            from_args(&["part2", "--json", "--bag", "1 red"]),
+            Some(String::from("Unknown argument: --bag"))
+        );
+        assert_eq!(
+            from_args(&["part2", "in.txt", "other.txt"]),
+            Some(String::from("Unexpected argument: other.txt"))
+        );
+        assert_eq!(
+            from_args(&["part2", "--unfold"]),
+            Some(String::from("Missing value for --unfold"))
+        );
+
+        // output.txt is only updated with the answer of the puzzle
+        let crate_dir = std::env::temp_dir().join(format!("test_utils_{}", std::process::id()));
+        fs::create_dir_all(crate_dir.join("src/bin/part2")).unwrap();
+        fs::write(crate_dir.join("src/bin/part2/output.txt"), "0").unwrap();
+        let crate_dir: &str = crate_dir.to_str().unwrap();
+        let output_file = |command: &[&str]| {
+            let io = SolverIo::from_args(&args(command), crate_dir, "part2", FLAGS).unwrap();
+            io.output_file("output.txt")
+        };
+        assert_eq!(
+            output_file(&["part2", "--trace"]),
+            Some(format!("{}/src/bin/part2/output.txt", crate_dir))
+        );
+        assert_eq!(output_file(&["part2", "--unfold", "2"]), None);
+        assert_eq!(output_file(&["part2", "-"]), None);
+        let io = SolverIo::from_args(&args(&["part1"]), crate_dir, "part1", FLAGS).unwrap();
+        assert_eq!(io.output_file("output.txt"), None);
+        fs::remove_dir_all(crate_dir).unwrap();
+    }
 }